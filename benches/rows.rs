@@ -0,0 +1,97 @@
+use std::{
+    io,
+    time::{Duration,Instant},
+};
+
+use tech_ui::{TableBuilder,TableDrawer,SoftColumn,RowOptions,RowRef};
+
+const ROWS: usize = 50_000;
+const RUNS: usize = 7;
+
+// The string building of row() before the templates: the divs of the row concatenated
+// with the values into a fresh String. The divs and the value order are cut from a row
+// rendered with markers.
+fn concat_divs(tb: &TableBuilder, row: RowRef, cnt: usize) -> Vec<(String,Option<usize>)> {
+    let marks = (0 .. cnt).map(|i| format!("\u{1}{}\u{1}",i)).collect::<Vec<_>>();
+    let html = tb.row(row,"bench_row",marks,&mut TableDrawer::new()).unwrap();
+    let parts = html.split('\u{1}').collect::<Vec<_>>();
+    parts.chunks(2).map(|p| (p[0].to_string(),p.get(1).map(|i| i.parse().unwrap()))).collect()
+}
+fn concat_row(divs: &[(String,Option<usize>)], values: Vec<String>) -> String {
+    let mut res = String::new();
+    for (d,arg) in divs {
+        res += d;
+        if let Some(v) = arg.and_then(|i| values.get(i)) { res += v; }
+    }
+    res
+}
+
+// a sink counting the bytes
+#[derive(Debug)]
+struct Count(usize);
+impl io::Write for Count {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// median and best of the runs, after one warm-up run
+fn measure<F: FnMut() -> usize>(mut f: F) -> (usize,Duration,Duration) {
+    let total = f();
+    let mut times = (0 .. RUNS).map(|_| {
+        let start = Instant::now();
+        assert_eq!(f(),total);
+        start.elapsed()
+    }).collect::<Vec<_>>();
+    times.sort();
+    (total,times[RUNS / 2],times[0])
+}
+
+fn main() {
+    let mut tb = TableBuilder::new();
+    let fixed = tb.table_fixed("bench_f",900).unwrap();
+    let fixed = tb.create_row_fixed(fixed,&[None,Some(80),Some(80),Some(120)]).unwrap();
+    let soft = tb.table_soft("bench_s",900).unwrap();
+    let soft = tb.create_row_soft(soft,vec![SoftColumn { percentage: None, subcolumns: vec![Some(80),None,Some(80),Some(120)] }]).unwrap();
+    let data = (0 .. ROWS).map(|i| [format!("request #{}",i),i.to_string(),(i % 7).to_string(),"2024-01-01 00:00:00".to_string()]).collect::<Vec<_>>();
+
+    println!("{} rows, median and best of {} runs",ROWS,RUNS);
+    for (name,row) in [("fixed",fixed),("soft",soft)] {
+        let mut drawer = TableDrawer::new();
+        let opts = RowOptions::new();
+        let divs = concat_divs(&tb,row,4);
+        assert_eq!(concat_row(&divs,data[0].to_vec()),tb.row(row,"bench_row",data[0].to_vec(),&mut drawer).unwrap());
+
+        let (total,base,best) = measure(|| data.iter().map(|d| {
+            tb.prepare_row(row,&opts,&mut drawer).unwrap();
+            concat_row(&divs,d.to_vec()).len()
+        }).sum());
+        println!("{:>5}: concat     {:>9} bytes {:>10.2?} {:>10.2?}",name,total,base,best);
+
+        let report = |what: &str, (total,median,best): (usize,Duration,Duration)| {
+            println!("{:>5}: {:<10} {:>9} bytes {:>10.2?} {:>10.2?} ({:.1}x)",name,what,total,median,best,base.as_secs_f64() / median.as_secs_f64());
+        };
+        report("row",measure(|| data.iter().map(|d| tb.row(row,"bench_row",d.to_vec(),&mut drawer).unwrap().len()).sum()));
+
+        let mut buf = String::new();
+        let mut drawer = TableDrawer::new();
+        report("write_row",measure(|| data.iter().map(|d| {
+            buf.clear();
+            tb.write_row(&mut buf,row,"bench_row",&opts,d,&mut drawer).unwrap();
+            buf.len()
+        }).sum()));
+
+        let mut drawer = TableDrawer::new();
+        report("write_io",measure(|| {
+            let mut out = io::BufWriter::new(Count(0));
+            for d in &data {
+                tb.write_row_io(&mut out,row,"bench_row",&opts,d,&mut drawer).unwrap();
+            }
+            out.into_inner().unwrap().0
+        }));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    io, fs,
+    path::Path,
+    sync::Arc,
+};
+
+use super::{Element,fnv64};
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ScriptLoad {
+    Blocking,
+    Defer,
+    Async,
+}
+
+// <script src> of the head, written before the inline scripts so they can use it
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Script {
+    src: String,
+    load: ScriptLoad,
+    module: bool,
+}
+impl Script {
+    pub fn new<T: ToString>(src: T) -> Script {
+        Script {
+            src: src.to_string(),
+            load: ScriptLoad::Blocking,
+            module: false,
+        }
+    }
+    pub fn defer(mut self) -> Script {
+        self.load = ScriptLoad::Defer;
+        self
+    }
+    pub fn asynchronous(mut self) -> Script {
+        self.load = ScriptLoad::Async;
+        self
+    }
+    // modules are deferred by the browser anyway
+    pub fn module(mut self) -> Script {
+        self.module = true;
+        self
+    }
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+    pub fn element(&self) -> Element {
+        let mut el = Element::new("script").attr("src",&self.src);
+        if self.module { el = el.attr("type","module"); }
+        match self.load {
+            ScriptLoad::Blocking => el,
+            ScriptLoad::Defer => el.flag("defer"),
+            ScriptLoad::Async => el.flag("async"),
+        }
+    }
+}
+
+fn content_type(name: &str) -> &'static str {
+    let ext = name.rsplit_once('.').map(|(_,e)| e.to_ascii_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "html" => "text/html; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+#[derive(Debug,Clone)]
+struct Asset {
+    name: String,
+    hashed: String,
+    hash: u64,
+    data: Arc<[u8]>,
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct AssetResponse {
+    pub status: u16,
+    pub headers: Vec<(&'static str,String)>,
+    pub body: Arc<[u8]>,
+}
+
+// Shared css, js and images served by the application: "app.css" is linked as
+// "{prefix}/app.{hash}.css", so the url changes with the content and can be cached for good.
+#[derive(Debug,Clone)]
+pub struct AssetRegistry {
+    prefix: String,
+    assets: Vec<Asset>,
+}
+impl AssetRegistry {
+    pub fn new<T: ToString>(prefix: T) -> AssetRegistry {
+        AssetRegistry {
+            prefix: prefix.to_string().trim_end_matches('/').to_string(),
+            assets: Vec::new(),
+        }
+    }
+    // an asset of the same name is replaced, the new url is returned
+    pub fn register<N: ToString>(&mut self, name: N, data: Vec<u8>) -> String {
+        let name = name.to_string().trim_start_matches('/').to_string();
+        let hash = fnv64(&data);
+        let hashed = match name.rsplit_once('.') {
+            Some((stem,ext)) if !stem.is_empty() && !ext.contains('/') => format!("{}.{:016x}.{}",stem,hash,ext),
+            _ => format!("{}.{:016x}",name,hash),
+        };
+        let asset = Asset { name, hashed, hash, data: data.into() };
+        let url = format!("{}/{}",self.prefix,asset.hashed);
+        match self.assets.iter().position(|a| a.name == asset.name) {
+            Some(i) => self.assets[i] = asset,
+            None => self.assets.push(asset),
+        }
+        url
+    }
+    // registered under the file name
+    pub fn register_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<String> {
+        let path = path.as_ref();
+        let name = match path.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput,"no file name")),
+        };
+        let data = fs::read(path)?;
+        Ok(self.register(name,data))
+    }
+    pub fn url(&self, name: &str) -> Option<String> {
+        self.get(name).map(|a| format!("{}/{}",self.prefix,a.hashed))
+    }
+    pub fn script(&self, name: &str) -> Option<Script> {
+        self.url(name).map(Script::new)
+    }
+    fn get(&self, name: &str) -> Option<&Asset> {
+        let name = name.trim_start_matches('/');
+        self.assets.iter().find(|a| a.name == name)
+    }
+
+    // Request path to response: hashed urls are immutable, plain names are revalidated by etag.
+    // None for paths outside the prefix and unknown (or outdated) assets.
+    pub fn serve(&self, path: &str, if_none_match: Option<&str>) -> Option<AssetResponse> {
+        let path = path.split(['?','#']).next().unwrap_or("");
+        let rest = path.strip_prefix(&self.prefix)?.strip_prefix('/')?;
+        let (asset,cache) = match self.assets.iter().find(|a| a.hashed == rest) {
+            Some(a) => (a,"public, max-age=31536000, immutable"),
+            None => (self.assets.iter().find(|a| a.name == rest)?,"no-cache"),
+        };
+        let etag = format!("\"{:016x}\"",asset.hash);
+        let mut headers = vec![
+            ("Cache-Control",cache.to_string()),
+            ("ETag",etag.clone()),
+        ];
+        let matched = if_none_match.map(|v| v.split(',').any(|t| { let t = t.trim(); (t == "*") || (t.trim_start_matches("W/") == etag) })).unwrap_or(false);
+        Some(match matched {
+            true => AssetResponse { status: 304, headers, body: Arc::from(Vec::new()) },
+            false => {
+                headers.push(("Content-Type",content_type(&asset.name).to_string()));
+                headers.push(("X-Content-Type-Options","nosniff".to_string()));
+                AssetResponse { status: 200, headers, body: asset.data.clone() }
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assets() {
+        assert_eq!(fnv64(b""),0xcbf29ce484222325);
+        assert_eq!(fnv64(b"a"),0xaf63dc4c8601ec8c);
+
+        let mut reg = AssetRegistry::new("/static/");
+        let url = reg.register("app.css",b"body { margin: 0; }".to_vec());
+        assert_eq!(url,format!("/static/app.{:016x}.css",fnv64(b"body { margin: 0; }")));
+        assert_eq!(reg.url("app.css"),Some(url.clone()));
+        reg.register("LICENSE",b"MIT".to_vec());
+        assert!(reg.url("LICENSE").unwrap().starts_with("/static/LICENSE."));
+
+        let r = reg.serve(&format!("{}?v=1",url),None).unwrap();
+        assert_eq!(r.status,200);
+        assert_eq!(&*r.body,b"body { margin: 0; }");
+        assert!(r.headers.contains(&("Cache-Control","public, max-age=31536000, immutable".to_string())));
+        assert!(r.headers.contains(&("Content-Type","text/css; charset=utf-8".to_string())));
+        let etag = r.headers.iter().find(|(n,_)| *n == "ETag").unwrap().1.clone();
+        let r = reg.serve("/static/app.css",Some(&format!("W/{}, \"x\"",etag))).unwrap();
+        assert_eq!((r.status,r.body.len()),(304,0));
+        assert!(r.headers.contains(&("Cache-Control","no-cache".to_string())));
+
+        // a new version changes the url, the old one is gone
+        let next = reg.register("app.css",b"body { margin: 1px; }".to_vec());
+        assert_ne!(next,url);
+        assert!(reg.serve(&url,None).is_none());
+        assert!(reg.serve("/other/app.css",None).is_none());
+
+        assert_eq!(reg.script("app.js"),None);
+        assert_eq!(Script::new("/a.js").defer().element().to_string(),"<script src='/a.js' defer></script>");
+        assert_eq!(Script::new("/m.js").module().asynchronous().element().to_string(),"<script src='/m.js' type='module' async></script>");
+    }
+}
@@ -0,0 +1,40 @@
+use super::Element;
+
+// styles and scripts shared by every instance of a component, a page includes each id once
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Dependency {
+    pub id: String,
+    pub css: String,
+    pub js: String,
+    // only on pages with debug layout
+    pub debug_css: String,
+}
+impl Dependency {
+    pub fn new<T: ToString>(id: T) -> Dependency {
+        Dependency {
+            id: id.to_string(),
+            css: String::new(),
+            js: String::new(),
+            debug_css: String::new(),
+        }
+    }
+    pub fn css(mut self, css: &str) -> Dependency {
+        self.css += css;
+        self
+    }
+    pub fn js(mut self, js: &str) -> Dependency {
+        self.js += js;
+        self
+    }
+    pub fn debug_css(mut self, css: &str) -> Dependency {
+        self.debug_css += css;
+        self
+    }
+}
+
+pub trait Component {
+    fn render(&self) -> Element;
+    fn dependencies(&self) -> Vec<Dependency> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use super::{
+    document::base64,
+    markup::{AttrWriter,AttrFilter},
+};
+
+// 128 bits from the random source of the os, Csp::with_nonce takes one of the application
+fn random_nonce() -> String {
+    let mut buf = [0u8; 16];
+    getrandom::getrandom(&mut buf).expect("os random source for the csp nonce");
+    base64(&buf)
+}
+
+// Content-Security-Policy of a page: inline <style> and <script> get the nonce, inline
+// on* handlers are moved to a nonce'd script. Create one per response, the nonce must not repeat.
+// Inline style='..' attributes (Block::style, Element::style) are allowed by style-src-attr 'unsafe-inline',
+// drop it with without("style-src-attr") on pages without them.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Csp {
+    nonce: String,
+    directives: Vec<(String,Vec<String>)>,
+}
+impl Default for Csp {
+    fn default() -> Csp {
+        let d = |name: &str, sources: &[&str]| (name.to_string(),sources.iter().map(|s| s.to_string()).collect());
+        Csp {
+            nonce: random_nonce(),
+            directives: vec![
+                d("default-src",&["'self'"]),
+                d("script-src",&["'strict-dynamic'"]),
+                d("style-src",&["'self'"]),
+                d("style-src-attr",&["'unsafe-inline'"]),
+                d("img-src",&["'self'","data:"]),
+                d("object-src",&["'none'"]),
+                d("base-uri",&["'none'"]),
+                d("form-action",&["'self'"]),
+                d("frame-ancestors",&["'self'"]),
+            ],
+        }
+    }
+}
+impl Csp {
+    pub const HEADER: &'static str = "Content-Security-Policy";
+
+    pub fn new() -> Csp {
+        Csp::default()
+    }
+    // a nonce from the application's own generator
+    pub fn with_nonce<T: ToString>(mut self, nonce: T) -> Csp {
+        self.nonce = nonce.to_string().chars().filter(|c| c.is_ascii_alphanumeric() || "+/=-_".contains(*c)).collect();
+        self
+    }
+    // adds a source to the directive, 'none' is dropped once there is another source
+    pub fn directive<N: ToString, S: ToString>(mut self, name: N, source: S) -> Csp {
+        let (name,source) = (name.to_string(),source.to_string());
+        let sources = match self.directives.iter().position(|(n,_)| *n == name) {
+            Some(i) => &mut self.directives[i].1,
+            None => {
+                self.directives.push((name,Vec::new()));
+                &mut self.directives.last_mut().unwrap().1
+            },
+        };
+        if source.is_empty() || sources.contains(&source) { return self; }
+        sources.retain(|s| s != "'none'");
+        sources.push(source);
+        self
+    }
+    pub fn without<N: AsRef<str>>(mut self, name: N) -> Csp {
+        self.directives.retain(|(n,_)| n != name.as_ref());
+        self
+    }
+    pub fn nonce(&self) -> &str {
+        &self.nonce
+    }
+    pub fn header_value(&self) -> String {
+        let mut res = String::new();
+        for (name,sources) in &self.directives {
+            if !res.is_empty() { res += "; "; }
+            res += name;
+            if (name == "script-src") || (name == "style-src") {
+                res += " 'nonce-";
+                res += &self.nonce;
+                res += "'";
+            }
+            for s in sources {
+                res.push(' ');
+                res += s;
+            }
+        }
+        res
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&quot;","\"").replace("&#39;","'").replace("&#x27;","'").replace("&lt;","<").replace("&gt;",">").replace("&amp;","&")
+}
+
+// on<event>='..' becomes data-ui-on<event>='{prefix}{id}', same code same id
+struct Handlers {
+    prefix: String,
+    handlers: Vec<String>,
+    events: Vec<String>,
+}
+impl AttrFilter for Handlers {
+    fn attr(&mut self, name: &str, value: &str) -> Option<(String,String)> {
+        let event = name.get(..2).filter(|on| on.eq_ignore_ascii_case("on")).map(|_| name[2..].to_ascii_lowercase())?;
+        if event.is_empty() || !event.chars().all(|c| c.is_ascii_alphabetic()) { return None; }
+        let code = unescape(value);
+        let id = match self.handlers.iter().position(|h| *h == code) {
+            Some(id) => id,
+            None => { self.handlers.push(code); self.handlers.len() - 1 },
+        };
+        let res = (format!("data-ui-on{}",event),format!("{}{}",self.prefix,id));
+        if !self.events.contains(&event) { self.events.push(event); }
+        Some(res)
+    }
+}
+
+// Moves inline on* handlers of the tags passing through to a delegating script:
+// onclick='go()' becomes data-ui-onclick='0', finish() returns the script.
+// The scripts share one listener per event, a prefixed writer ('f1_0') keeps its handlers apart.
+pub(crate) struct HandlerWriter<'a, W: fmt::Write + ?Sized>(AttrWriter<'a,W,Handlers>);
+impl<'a, W: fmt::Write + ?Sized> HandlerWriter<'a,W> {
+    pub(crate) fn new(inner: &'a mut W) -> HandlerWriter<'a,W> {
+        HandlerWriter(AttrWriter::new(inner,Handlers { prefix: String::new(), handlers: Vec::new(), events: Vec::new() }))
+    }
+    // alphanumeric, the ids get a '_' after it
+    pub(crate) fn with_prefix(mut self, prefix: &str) -> HandlerWriter<'a,W> {
+        let h = &mut self.0.filter;
+        h.prefix = prefix.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        if !h.prefix.is_empty() { h.prefix += "_"; }
+        self
+    }
+    pub(crate) fn finish(self) -> Result<String,fmt::Error> {
+        let h = self.0.finish()?;
+        if h.handlers.is_empty() { return Ok(String::new()); }
+        let mut res = format!("(function() {{\n    var all = window.uiHandlers = window.uiHandlers || {{}};\n    all['{}'] = [\n",h.prefix);
+        for code in &h.handlers {
+            res += "        function(event) { ";
+            res += &code.replace("</","<\\/");
+            res += "\n        },\n";
+        }
+        // one listener per event for the page, the ids name their handler set: '{prefix}_{n}' or '{n}'
+        res += "    ];
+    window.uiRun = window.uiRun || function(e) {
+        var a = 'data-ui-on' + e.type;
+        for (var el = e.target; el && el.getAttribute && !e.cancelBubble; el = e.bubbles ? el.parentNode : null) {
+            var id = el.getAttribute(a);
+            var i = id ? id.lastIndexOf('_') + 1 : 0;
+            var hs = id ? all[id.slice(0, i)] : null;
+            var h = hs ? hs[id.slice(i)] : null;
+            if (h && (h.call(el, e) === false)) { e.preventDefault(); }
+        }
+    };
+    var seen = window.uiEvents = window.uiEvents || {};\n    [";
+        for (i,e) in h.events.iter().enumerate() {
+            if i > 0 { res += ","; }
+            res += &format!("'{}'",e);
+        }
+        res += "].forEach(function(t) { if (!seen[t]) { seen[t] = true; document.addEventListener(t, window.uiRun, true); } });\n})();\n";
+        Ok(res)
+    }
+}
+impl<W: fmt::Write + ?Sized> fmt::Write for HandlerWriter<'_,W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fmt::Write;
+
+    #[test]
+    fn csp() {
+        let a = Csp::new();
+        assert_ne!(a.nonce(),Csp::new().nonce());
+        assert_eq!(a.nonce().len(),24);
+        let c = Csp::new().with_nonce("abc'd").directive("img-src","https://cdn.example").directive("object-src","'self'")
+            .directive("report-uri","/csp").without("frame-ancestors");
+        assert_eq!(c.header_value(),"default-src 'self'; script-src 'nonce-abcd' 'strict-dynamic'; style-src 'nonce-abcd' 'self'; \
+style-src-attr 'unsafe-inline'; img-src 'self' data: https://cdn.example; object-src 'self'; base-uri 'none'; form-action 'self'; report-uri /csp");
+
+        let mut out = String::new();
+        let mut w = HandlerWriter::new(&mut out);
+        for chunk in ["<div onclick='go(&quot;1&quot;)' data-x='a onclick=b'>a on",
+                      "click='no'<a onmou","seover=\"hl(this)\" onclick='go(&quot;1&quot;)'>x</a>",
+                      "<script>el.innerHTML = \"<b onclick='y()'>\";</script><!-- <i onclick='z()'> --><i onx"] {
+            w.write_str(chunk).unwrap();
+        }
+        let script = w.finish().unwrap();
+        assert_eq!(out,"<div data-ui-onclick='0' data-x='a onclick=b'>a onclick='no'<a data-ui-onmouseover=\"1\" data-ui-onclick='0'>x</a>\
+<script>el.innerHTML = \"<b onclick='y()'>\";</script><!-- <i onclick='z()'> --><i onx");
+        assert!(!script.contains("no") && !script.contains("y()") && !script.contains("z()"));
+        assert!(script.contains("        function(event) { go(\"1\")\n        },\n        function(event) { hl(this)\n        },\n    ];\n"));
+        assert!(script.contains("['click','mouseover'].forEach("));
+    }
+}
@@ -0,0 +1,350 @@
+pub(crate) type Decls = Vec<(String,String)>;
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub(crate) enum CssItem {
+    Rule {
+        selectors: Vec<String>,
+        decls: Vec<(String,String)>,
+    },
+    // @-rules are kept verbatim
+    At(String),
+}
+
+// Chars outside strings and parentheses with their byte offsets, so that separators in
+// content: "};" or url(data:image/png;base64,..) are not taken for structure.
+fn top_level(css: &str) -> impl Iterator<Item = (usize,char)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut parens = 0usize;
+    css.char_indices().filter(move |&(_,c)| {
+        if escaped {
+            escaped = false;
+            return false;
+        }
+        if c == '\\' {
+            escaped = true;
+            return false;
+        }
+        match (quote,c) {
+            (Some(q),c) => { if c == q { quote = None; } false },
+            (None,'"') | (None,'\'') => { quote = Some(c); false },
+            (None,'(') => { parens += 1; false },
+            (None,')') => { parens = parens.saturating_sub(1); false },
+            (None,_) => parens == 0,
+        }
+    })
+}
+
+fn find_top(css: &str, c: char) -> Option<usize> {
+    top_level(css).find(|(_,t)| *t == c).map(|(i,_)| i)
+}
+
+fn split_top(css: &str, sep: char) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut start = 0;
+    for (i,_) in top_level(css).filter(|(_,c)| *c == sep) {
+        res.push(&css[start..i]);
+        start = i + sep.len_utf8();
+    }
+    res.push(&css[start..]);
+    res
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut res = String::with_capacity(css.len());
+    let mut quote = None;
+    let mut escaped = false;
+    let mut chars = css.char_indices().peekable();
+    while let Some((i,c)) = chars.next() {
+        match (quote,c) {
+            _ if escaped => escaped = false,
+            (_,'\\') => escaped = true,
+            (Some(q),c) if c == q => quote = None,
+            (Some(_),_) => {},
+            (None,'"') | (None,'\'') => quote = Some(c),
+            (None,'/') if css[i..].starts_with("/*") => {
+                match css[i+2..].find("*/") {
+                    Some(j) => while chars.peek().map(|(k,_)| *k < i + 2 + j + 2).unwrap_or(false) { chars.next(); },
+                    None => break,
+                }
+                continue;
+            },
+            _ => {},
+        }
+        res.push(c);
+    }
+    res
+}
+
+pub(crate) fn parse_decls(block: &str) -> Vec<(String,String)> {
+    split_top(block,';').into_iter().filter_map(|d| {
+        let i = d.find(':')?;
+        let (p,v) = (d[..i].trim(),d[i+1..].trim());
+        match p.is_empty() || v.is_empty() {
+            true => None,
+            false => Some((p.to_string(),v.to_string())),
+        }
+    }).collect()
+}
+
+pub(crate) fn parse_items(css: &str) -> Vec<CssItem> {
+    let css = strip_comments(css);
+    let mut res = Vec::new();
+    let mut rest = css.as_str();
+    // statement @-rules (@import ..;) end with a semicolon instead of a block
+    let statements = |text: &str, res: &mut Vec<CssItem>| -> usize {
+        let mut used = 0;
+        while let Some(i) = find_top(&text[used..],';') {
+            let st = text[used .. used + i].trim();
+            if !st.is_empty() { res.push(CssItem::At(format!("{};",st))); }
+            used += i + 1;
+        }
+        used
+    };
+    while let Some(open) = find_top(rest,'{') {
+        let used = statements(&rest[..open],&mut res);
+        let head = rest[used..open].trim();
+        let mut depth = 0;
+        let mut close = None;
+        for (i,c) in top_level(&rest[open..]) {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 { close = Some(open + i); break; }
+                },
+                _ => {},
+            }
+        }
+        let close = close.unwrap_or(rest.len());
+        let body = &rest[open+1 .. close];
+        match head.starts_with('@') {
+            true => res.push(CssItem::At(format!("{} {{{}}}",head,body))),
+            false => res.push(CssItem::Rule {
+                selectors: split_top(head,',').into_iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+                decls: parse_decls(body),
+            }),
+        }
+        rest = match close < rest.len() {
+            true => &rest[close+1..],
+            false => "",
+        };
+    }
+    statements(rest,&mut res);
+    res
+}
+
+fn is_class_selector(sel: &str) -> bool {
+    match sel.strip_prefix('.') {
+        Some(c) => !c.is_empty() && c.chars().all(|c| c.is_alphanumeric() || (c == '_') || (c == '-')),
+        None => false,
+    }
+}
+
+fn set(decls: &mut Vec<(String,String)>, prop: &str, value: &str) {
+    match decls.iter_mut().find(|(p,_)| p == prop) {
+        Some(d) => d.1 = value.to_string(),
+        None => decls.push((prop.to_string(),value.to_string())),
+    }
+}
+
+const SIDES: [&str; 4] = ["top","right","bottom","left"];
+
+fn expand(decls: &mut Vec<(String,String)>, prop: &str, value: &str) {
+    match prop {
+        "padding" | "margin" => {
+            let v = value.split_whitespace().collect::<Vec<_>>();
+            let v = match v.len() {
+                1 => [v[0],v[0],v[0],v[0]],
+                2 => [v[0],v[1],v[0],v[1]],
+                3 => [v[0],v[1],v[2],v[1]],
+                4 => [v[0],v[1],v[2],v[3]],
+                _ => { set(decls,prop,value); return; },
+            };
+            for (side,v) in SIDES.iter().zip(v) {
+                set(decls,&format!("{}-{}",prop,side),v);
+            }
+        },
+        _ => set(decls,prop,value),
+    }
+}
+
+fn is_zero(v: &str) -> bool {
+    v.trim_end_matches("px") == "0"
+}
+
+fn compact(decls: Vec<(String,String)>) -> Vec<(String,String)> {
+    // div margins default to zero
+    let mut res = decls.into_iter().filter(|(p,v)| !(p.starts_with("margin-") && is_zero(v))).collect::<Vec<_>>();
+    // longhands collapse into the shorthand when all four sides are set
+    for short in ["padding","margin"] {
+        let vals = SIDES.iter().map(|side| decls_value(&res,&format!("{}-{}",short,side)).map(|v| v.to_string())).collect::<Option<Vec<_>>>();
+        if let Some(v) = vals {
+            let value = match (v[0] == v[2], v[1] == v[3], v[0] == v[1]) {
+                (true,true,true) => v[0].clone(),
+                (true,true,false) => format!("{} {}",v[0],v[1]),
+                (false,true,_) => format!("{} {} {}",v[0],v[1],v[2]),
+                _ => v.join(" "),
+            };
+            let long = format!("{}-",short);
+            let pos = res.iter().position(|(p,_)| p.starts_with(&long)).unwrap_or(0);
+            res.retain(|(p,_)| !p.starts_with(&long));
+            res.insert(pos,(short.to_string(),value));
+        }
+    }
+    res
+}
+
+fn decls_value<'a>(decls: &'a [(String,String)], prop: &str) -> Option<&'a str> {
+    decls.iter().find(|(p,_)| p == prop).map(|(_,v)| v.as_str())
+}
+
+// Merges rules of plain class selectors: declarations are resolved per class (later ones win),
+// then classes with identical declarations share one rule. A class rule only moves up past
+// other rules that set none of its properties, @-rules are never passed, so the cascade against
+// them is kept. Classes are assumed to be on different elements, as in the styles of TableBuilder.
+pub fn optimize_css(css: &str, minify: bool) -> String {
+    // a class or the classes of a group
+    enum Entry<C> {
+        Class(C,Decls),
+        // the properties of other rules, None for @-rules
+        Other(CssItem,Option<Decls>),
+    }
+    fn blocks<C>(entries: &[Entry<C>], decls: &Decls) -> bool {
+        entries.iter().any(|e| match e {
+            Entry::Class(..) => false,
+            Entry::Other(_,None) => true,
+            Entry::Other(_,Some(d)) => d.iter().any(|(p,_)| decls.iter().any(|(q,_)| p == q)),
+        })
+    }
+    let mut entries: Vec<Entry<String>> = Vec::new();
+    for item in parse_items(css) {
+        match item {
+            CssItem::Rule { selectors, decls } if selectors.iter().all(|s| is_class_selector(s)) => {
+                let mut expanded = Vec::new();
+                for (p,v) in &decls {
+                    expand(&mut expanded,p,v);
+                }
+                for sel in selectors {
+                    let idx = entries.iter().rposition(|e| matches!(e,Entry::Class(c,_) if *c == sel))
+                        .filter(|i| !blocks(&entries[i + 1 ..],&expanded));
+                    match idx.map(|i| &mut entries[i]) {
+                        Some(Entry::Class(_,d)) => for (p,v) in &expanded { expand(d,p,v); },
+                        _ => entries.push(Entry::Class(sel,expanded.clone())),
+                    }
+                }
+            },
+            CssItem::Rule { selectors, decls } => {
+                let mut expanded = Vec::new();
+                for (p,v) in &decls {
+                    expand(&mut expanded,p,v);
+                }
+                entries.push(Entry::Other(CssItem::Rule { selectors, decls },Some(expanded)));
+            },
+            item => entries.push(Entry::Other(item,None)),
+        }
+    }
+    let mut groups: Vec<Entry<Vec<String>>> = Vec::new();
+    for entry in entries {
+        let (cls,decls) = match entry {
+            Entry::Class(cls,decls) => (cls,decls),
+            Entry::Other(item,decls) => { groups.push(Entry::Other(item,decls)); continue; },
+        };
+        let compacted = compact(decls.clone());
+        if compacted.is_empty() { continue; }
+        let mut key = compacted.clone();
+        key.sort();
+        let found = groups.iter().rposition(|g| matches!(g,Entry::Class(_,d) if { let mut d = d.clone(); d.sort(); d == key }))
+            .filter(|i| !blocks(&groups[i + 1 ..],&decls));
+        match found.map(|i| &mut groups[i]) {
+            Some(Entry::Class(sels,_)) => sels.push(cls),
+            _ => groups.push(Entry::Class(vec![cls],compacted)),
+        }
+    }
+    let mut res = String::new();
+    for g in groups {
+        match (g,minify) {
+            (Entry::Class(sels,decls),true) => {
+                res += &sels.join(",");
+                res += "{";
+                res += &decls.iter().map(|(p,v)| format!("{}:{}",p,v)).collect::<Vec<_>>().join(";");
+                res += "}";
+            },
+            (Entry::Class(sels,decls),false) => {
+                res += &sels.join(", ");
+                res += " {";
+                for (p,v) in decls {
+                    res += &format!(" {}: {};",p,v);
+                }
+                res += " }\n";
+            },
+            (Entry::Other(item,_),_) => {
+                match item {
+                    CssItem::Rule { selectors, decls } => {
+                        let decls = decls.iter().map(|(p,v)| format!("{}: {};",p,v)).collect::<Vec<_>>().join(" ");
+                        res += &format!("{} {{ {} }}",selectors.join(", "),decls);
+                    },
+                    CssItem::At(at) => res += &at,
+                }
+                if !minify { res += "\n"; }
+            },
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optimize() {
+        let css = ".a, .b, .c { padding: 4px; float: left; }
+/* generated */
+.a, .b { padding-right: 2px; }
+.b, .c { padding-left: 2px; }
+.a { width: 10px; } .b { width: 10px; } .c { width: 10px; margin-left: 0px; }
+.d { width: 10px; padding-top: 4px; padding-right: 4px; padding-bottom: 4px; padding-left: 2px; float: left; }
+@media (max-width: 600px) { .a { display: none; } }
+";
+        assert_eq!(optimize_css(css,false),".a { padding: 4px 2px 4px 4px; float: left; width: 10px; }
+.b { padding: 4px 2px; float: left; width: 10px; }
+.c, .d { padding: 4px 4px 4px 2px; float: left; width: 10px; }
+@media (max-width: 600px) { .a { display: none; } }
+");
+        assert_eq!(optimize_css(".x { margin: 0 3px; } .y { margin-right: 3px; margin-left: 3px; }",true),".x,.y{margin-right:3px;margin-left:3px}");
+        // later class rules do not move past the rules they would override or be overridden by
+        let css = ".a { color: red; }
+@media (max-width: 600px) { .e { display: none; } }
+.e { display: block; }
+div > .b { color: blue; }
+.a { width: 1px; }
+.c { color: red; }
+.d { color: red; width: 1px; }
+.a { color: green; }
+";
+        assert_eq!(optimize_css(css,false),".a { color: red; }
+@media (max-width: 600px) { .e { display: none; } }
+.e { display: block; }
+div > .b { color: blue; }
+.a { width: 1px; color: green; }
+.c { color: red; }
+.d { color: red; width: 1px; }
+");
+        assert_eq!(optimize_css(".x { color: red; } p { margin: 0; } .x { width: 1px; } p { color: blue; } .y { color: red; width: 1px; }",false),
+                   ".x { color: red; width: 1px; }\np { margin: 0; }\np { color: blue; }\n.y { color: red; width: 1px; }\n");
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(parse_items(".a { background: url(data:image/png;base64,AAAA); color: red; }"),vec![CssItem::Rule {
+            selectors: vec![".a".to_string()],
+            decls: vec![("background".to_string(),"url(data:image/png;base64,AAAA)".to_string()),("color".to_string(),"red".to_string())],
+        }]);
+        assert_eq!(parse_items(".a::after { content: \"}; /* x */\"; } .b, :is(.c, .d) { content: '\\'{'; } /* .e { } */"),vec![
+            CssItem::Rule { selectors: vec![".a::after".to_string()], decls: vec![("content".to_string(),"\"}; /* x */\"".to_string())] },
+            CssItem::Rule { selectors: vec![".b".to_string(),":is(.c, .d)".to_string()], decls: vec![("content".to_string(),"'\\'{'".to_string())] },
+        ]);
+        assert_eq!(parse_items("@import url(\"a;b.css\"); .x { y: z; }")[0],CssItem::At("@import url(\"a;b.css\");".to_string()));
+    }
+}
@@ -0,0 +1,97 @@
+use super::{Rule,Selector,Pseudo,Nth};
+
+// The look on top of the components' own css: tabs, table rows, row details, bulk actions and
+// cell editing. Opt-in, colors come from the theme variables with fallbacks for pages without a theme.
+// The page writes them after the css of its components, so a rule here wins over a component rule.
+//
+// Rows get their look from the class passed to TableBuilder::row: "table_row" or "table_header".
+
+#[derive(Debug,Clone)]
+pub struct DefaultStyles {
+    rules: Vec<Rule>,
+}
+impl Default for DefaultStyles {
+    fn default() -> DefaultStyles {
+        let border = "1px solid var(--ui-border, #d0d7de)";
+        let class = Selector::class;
+        let hover = |c: &str| Selector::class(c).pseudo(Pseudo::Hover);
+        let tab = |c: &str| Rule::new(Selector::class(c)).decl("padding","var(--ui-spacing, 4px) 12px").decl("margin-right","2px").decl("border",border)
+            .decl("border-bottom","none").decl("border-radius","var(--ui-radius, 4px) var(--ui-radius, 4px) 0 0");
+        let count = |c: &str| Rule::new(Selector::class(c)).decl("margin-left","6px").decl("padding","0 4px").decl("font-size","80%");
+        DefaultStyles {
+            rules: vec![
+                Rule::new(class("tabs")).decl("margin-bottom","8px"),
+                Rule::new(class("tab_row")).decl("padding","0 4px"),
+                tab("tab_button").decl("cursor","pointer").decl("color","var(--ui-muted, #6b7280)").decl("background","var(--ui-surface, #f3f4f6)"),
+                tab("tab_button_active").decl("color","var(--ui-text, #1f2328)").decl("background","var(--ui-bg, #fff)").decl("font-weight","bold"),
+                Rule::new(hover("tab_button")).decl("color","var(--ui-text, #1f2328)"),
+                count("tab_count").decl("color","#fff").decl("background","var(--ui-accent, #2f6feb)").decl("border-radius","6px"),
+                count("tab_count_empty"),
+                Rule::new(class("tab_finish")).decl("border-bottom",border),
+
+                Rule::new(class("table_header")).decl("display","flow-root").decl("font-weight","bold").decl("color","var(--ui-muted, #6b7280)")
+                    .decl("border-bottom","2px solid var(--ui-border, #d0d7de)"),
+                Rule::new(class("table_row")).decl("display","flow-root").decl("border-bottom",border),
+                Rule::new(class("table_row").pseudo(Pseudo::NthChild(Nth::Even))).decl("background","var(--ui-row-alt, #f8f9fa)"),
+                Rule::new(hover("table_row")).decl("background","var(--ui-row-hover, #eef3fd)"),
+                Rule::new(class("row_detail")).decl("padding","8px 12px").decl("border-left","3px solid var(--ui-accent, #2f6feb)"),
+
+                Rule::new(class("bulk_actions").descendant(Selector::tag("button"))).decl("margin-right","4px").decl("padding","2px 10px").decl("border",border)
+                    .decl("border-radius","var(--ui-radius, 4px)").decl("color","inherit").decl("background","var(--ui-surface, #f3f4f6)").decl("cursor","pointer"),
+                Rule::new(hover("cell_edit")).decl("background","var(--ui-surface, #f3f4f6)"),
+                Rule::new(class("cell_saved")).decl("color","var(--ui-success, #1a7f37)"),
+            ],
+        }
+    }
+}
+impl DefaultStyles {
+    pub fn new() -> DefaultStyles {
+        DefaultStyles::default()
+    }
+    // the rule of the same selectors is replaced, a Style overrides its class;
+    // extend a default with get(..).cloned().decl(..)
+    pub fn with<R: Into<Rule>>(mut self, rule: R) -> DefaultStyles {
+        let rule = rule.into();
+        match self.rules.iter().position(|r| r.selectors() == rule.selectors()) {
+            Some(i) => self.rules[i] = rule,
+            None => self.rules.push(rule),
+        }
+        self
+    }
+    pub fn without(mut self, sel: &Selector) -> DefaultStyles {
+        self.rules.retain(|r| r.selectors() != std::slice::from_ref(sel));
+        self
+    }
+    pub fn get(&self, sel: &Selector) -> Option<&Rule> {
+        self.rules.iter().find(|r| r.selectors() == std::slice::from_ref(sel))
+    }
+    pub fn css(&self) -> String {
+        let mut res = String::new();
+        for r in &self.rules {
+            res += &r.to_string();
+            res += "\n";
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Style;
+
+    #[test]
+    fn defaults() {
+        let d = DefaultStyles::new();
+        let css = d.css();
+        assert!(css.contains(".tab_button:hover { color: var(--ui-text, #1f2328); }\n"));
+        assert!(css.contains(".table_row:nth-child(even) { background:"));
+        assert!(css.contains(".bulk_actions button { margin-right: 4px;"));
+        let row = d.get(&Selector::class("table_row")).cloned().unwrap().decl("border-bottom","none");
+        let d = d.with(row).without(&Selector::class("tabs")).with(Style::new("tab_row").opt("padding","0"));
+        let css = d.css();
+        assert!(css.contains(".table_row { display: flow-root; border-bottom: none; }\n"));
+        assert!(css.contains(".tab_row { padding: 0; }\n"));
+        assert!(!css.contains(".tabs {"));
+    }
+}
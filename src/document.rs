@@ -0,0 +1,203 @@
+use std::fmt;
+
+use super::{Element,write_escaped};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn base64(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0],*chunk.get(1).unwrap_or(&0),*chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as usize) << 16) | ((b[1] as usize) << 8) | (b[2] as usize);
+        for i in 0 .. 4 {
+            match i <= chunk.len() {
+                true => res.push(BASE64[(n >> (18 - 6 * i)) & 63] as char),
+                false => res.push('='),
+            }
+        }
+    }
+    res
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Favicon {
+    Url(String),
+    // inlined as a data uri
+    Data {
+        mime: String,
+        data: Vec<u8>,
+    },
+}
+impl Favicon {
+    pub fn url<T: ToString>(url: T) -> Favicon {
+        Favicon::Url(url.to_string())
+    }
+    pub fn data<T: ToString>(mime: T, data: Vec<u8>) -> Favicon {
+        Favicon::Data { mime: mime.to_string(), data }
+    }
+    pub fn svg(svg: &str) -> Favicon {
+        Favicon::data("image/svg+xml",svg.as_bytes().to_vec())
+    }
+    pub fn href(&self) -> String {
+        match self {
+            Favicon::Url(url) => url.clone(),
+            Favicon::Data { mime, data } => format!("data:{};base64,{}",mime,base64(data)),
+        }
+    }
+}
+
+// Everything of the page around the title: doctype, <html lang>, charset, viewport, favicon,
+// meta and link tags. The defaults give a standards mode utf-8 page for any screen.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Document {
+    doctype: Option<String>,
+    lang: Option<String>,
+    charset: Option<String>,
+    viewport: Option<String>,
+    description: Option<String>,
+    favicon: Option<Favicon>,
+    head: Vec<Element>,
+}
+impl Default for Document {
+    fn default() -> Document {
+        Document {
+            doctype: Some("html".to_string()),
+            lang: Some("en".to_string()),
+            charset: Some("utf-8".to_string()),
+            viewport: Some("width=device-width, initial-scale=1".to_string()),
+            description: None,
+            favicon: None,
+            head: Vec::new(),
+        }
+    }
+}
+impl Document {
+    pub fn new() -> Document {
+        Document::default()
+    }
+    // the page as before: no doctype, lang, charset or viewport
+    pub fn bare() -> Document {
+        Document {
+            doctype: None,
+            lang: None,
+            charset: None,
+            viewport: None,
+            description: None,
+            favicon: None,
+            head: Vec::new(),
+        }
+    }
+    pub fn doctype<T: ToString>(mut self, doctype: T) -> Document {
+        self.doctype = Some(doctype.to_string());
+        self
+    }
+    pub fn without_doctype(mut self) -> Document {
+        self.doctype = None;
+        self
+    }
+    pub fn lang<T: ToString>(mut self, lang: T) -> Document {
+        self.lang = Some(lang.to_string());
+        self
+    }
+    pub fn charset<T: ToString>(mut self, charset: T) -> Document {
+        self.charset = Some(charset.to_string());
+        self
+    }
+    pub fn viewport<T: ToString>(mut self, viewport: T) -> Document {
+        self.viewport = Some(viewport.to_string());
+        self
+    }
+    pub fn without_viewport(mut self) -> Document {
+        self.viewport = None;
+        self
+    }
+    pub fn description<T: ToString>(mut self, description: T) -> Document {
+        self.description = Some(description.to_string());
+        self
+    }
+    pub fn favicon(mut self, favicon: Favicon) -> Document {
+        self.favicon = Some(favicon);
+        self
+    }
+    pub fn meta<N: ToString, C: ToString>(self, name: N, content: C) -> Document {
+        self.head(Element::new("meta").attr("name",name).attr("content",content))
+    }
+    // open graph and the like: <meta property='og:title' ..>
+    pub fn meta_property<P: ToString, C: ToString>(self, property: P, content: C) -> Document {
+        self.head(Element::new("meta").attr("property",property).attr("content",content))
+    }
+    pub fn http_equiv<N: ToString, C: ToString>(self, name: N, content: C) -> Document {
+        self.head(Element::new("meta").attr("http-equiv",name).attr("content",content))
+    }
+    pub fn link<R: ToString, H: ToString>(self, rel: R, href: H) -> Document {
+        self.head(Element::new("link").attr("rel",rel).attr("href",href))
+    }
+    // any other tag of the head, after the generated ones
+    pub fn head(mut self, e: Element) -> Document {
+        self.head.push(e);
+        self
+    }
+
+    pub(crate) fn write_open(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        if let Some(d) = &self.doctype {
+            writeln!(f,"<!DOCTYPE {}>",d)?;
+        }
+        f.write_str("<html")?;
+        if let Some(lang) = &self.lang {
+            f.write_str(" lang='")?;
+            write_escaped(f,lang)?;
+            f.write_str("'")?;
+        }
+        f.write_str(">\n<head>\n")?;
+        if let Some(c) = &self.charset {
+            writeln!(f,"{}",Element::new("meta").attr("charset",c))?;
+        }
+        if let Some(v) = &self.viewport {
+            writeln!(f,"{}",Element::new("meta").attr("name","viewport").attr("content",v))?;
+        }
+        if let Some(d) = &self.description {
+            writeln!(f,"{}",Element::new("meta").attr("name","description").attr("content",d))?;
+        }
+        if let Some(icon) = &self.favicon {
+            writeln!(f,"{}",Element::new("link").attr("rel","icon").attr("href",icon.href()))?;
+        }
+        for e in &self.head {
+            writeln!(f,"{}",e)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document() {
+        assert_eq!(base64(b""),"");
+        assert_eq!(base64(b"f"),"Zg==");
+        assert_eq!(base64(b"fo"),"Zm8=");
+        assert_eq!(base64(b"foobar"),"Zm9vYmFy");
+        assert_eq!(base64(&[0xfb,0xff]),"+/8=");
+
+        let mut s = String::new();
+        Document::new().lang("de").description("Jobs & queues").favicon(Favicon::data("image/png",vec![0x89,0x50,0x4e,0x47]))
+            .meta("robots","noindex").meta_property("og:title","Jobs").link("stylesheet","/site.css?v='1'")
+            .write_open(&mut s).unwrap();
+        assert_eq!(s,"<!DOCTYPE html>
+<html lang='de'>
+<head>
+<meta charset='utf-8'>
+<meta name='viewport' content='width=device-width, initial-scale=1'>
+<meta name='description' content='Jobs &amp; queues'>
+<link rel='icon' href='data:image/png;base64,iVBORw=='>
+<meta name='robots' content='noindex'>
+<meta property='og:title' content='Jobs'>
+<link rel='stylesheet' href='/site.css?v=&#39;1&#39;'>
+");
+        let mut s = String::new();
+        Document::bare().write_open(&mut s).unwrap();
+        assert_eq!(s,"<html>\n<head>\n");
+        assert_eq!(Favicon::svg("<svg/>").href(),"data:image/svg+xml;base64,PHN2Zy8+");
+    }
+}
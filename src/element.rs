@@ -0,0 +1,356 @@
+use std::fmt::{self,Write};
+
+use super::{
+    write_escaped,Scope,
+    scope::ScopedWriter,
+};
+
+const VOID: [&str; 13] = ["area","base","br","col","embed","hr","img","input","link","meta","source","track","wbr"];
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Node {
+    // escaped on output
+    Text(String),
+    Raw(String),
+    Element(Element),
+}
+impl From<Element> for Node {
+    fn from(e: Element) -> Node {
+        Node::Element(e)
+    }
+}
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_scoped(f,None)
+    }
+}
+impl Node {
+    // raw markup gets the scope on the class attributes of its tags
+    pub(crate) fn write_scoped(&self, w: &mut dyn fmt::Write, scope: Option<&Scope>) -> fmt::Result {
+        match (self,scope) {
+            (Node::Text(t),_) => write_escaped(w,t),
+            (Node::Raw(r),None) => w.write_str(r),
+            (Node::Raw(r),Some(scope)) => {
+                let mut w = ScopedWriter::new(w,scope);
+                w.write_str(r)?;
+                w.finish()
+            },
+            (Node::Element(e),_) => e.write_scoped(w,scope),
+        }
+    }
+}
+
+// characters that would end or break a tag or attribute name are dropped
+fn write_name<W: fmt::Write + ?Sized>(w: &mut W, name: &str) -> fmt::Result {
+    for c in name.chars() {
+        if !(c.is_whitespace() || c.is_control() || "\"'<>/=&`".contains(c)) {
+            w.write_char(c)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Element {
+    tag: String,
+    id: Option<String>,
+    classes: Vec<String>,
+    // None is a boolean attribute
+    attrs: Vec<(String,Option<String>)>,
+    // inline style declarations
+    style: Vec<(String,String)>,
+    children: Vec<Node>,
+}
+impl Element {
+    pub fn new<T: ToString>(tag: T) -> Element {
+        Element {
+            tag: tag.to_string(),
+            id: None,
+            classes: Vec::new(),
+            attrs: Vec::new(),
+            style: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+    pub fn id<T: ToString>(mut self, id: T) -> Element {
+        self.id = Some(id.to_string());
+        self
+    }
+    pub fn class<T: ToString>(mut self, class: T) -> Element {
+        let class = class.to_string();
+        if !self.classes.contains(&class) {
+            self.classes.push(class);
+        }
+        self
+    }
+    pub fn attr<N: ToString, V: ToString>(mut self, name: N, value: V) -> Element {
+        self.put_attr(name.to_string(),Some(value.to_string()));
+        self
+    }
+    pub fn flag<N: ToString>(mut self, name: N) -> Element {
+        self.put_attr(name.to_string(),None);
+        self
+    }
+    pub fn classes_from<I: IntoIterator<Item = T>, T: ToString>(mut self, classes: I) -> Element {
+        for c in classes {
+            self = self.class(c);
+        }
+        self
+    }
+    pub fn data<N: fmt::Display, V: ToString>(self, name: N, value: V) -> Element {
+        self.attr(format!("data-{}",name),value)
+    }
+    // event without the "on" prefix: on("mouseover","hl(this)")
+    pub fn on<E: fmt::Display, H: ToString>(self, event: E, handler: H) -> Element {
+        self.attr(format!("on{}",event),handler)
+    }
+    pub fn style<P: ToString, V: ToString>(mut self, prop: P, value: V) -> Element {
+        let (prop,value) = (prop.to_string(),value.to_string());
+        match self.style.iter_mut().find(|(p,_)| *p == prop) {
+            Some(d) => d.1 = value,
+            None => self.style.push((prop,value)),
+        }
+        self
+    }
+    pub fn text<T: ToString>(mut self, t: T) -> Element {
+        self.children.push(Node::Text(t.to_string()));
+        self
+    }
+    pub fn raw<T: ToString>(mut self, r: T) -> Element {
+        self.children.push(Node::Raw(r.to_string()));
+        self
+    }
+    pub fn child<N: Into<Node>>(mut self, n: N) -> Element {
+        self.children.push(n.into());
+        self
+    }
+    pub fn push<N: Into<Node>>(&mut self, n: N) {
+        self.children.push(n.into());
+    }
+
+    fn put_attr(&mut self, name: String, value: Option<String>) {
+        match self.attrs.iter_mut().find(|(n,_)| *n == name) {
+            Some(a) => a.1 = value,
+            None => self.attrs.push((name,value)),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    pub fn get_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+    pub fn classes(&self) -> &[String] {
+        &self.classes
+    }
+    pub fn get_attr(&self, name: &str) -> Option<Option<&str>> {
+        self.attrs.iter().find(|(n,_)| n == name).map(|(_,v)| v.as_deref())
+    }
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+    pub fn is_void(&self) -> bool {
+        VOID.contains(&self.tag.as_str())
+    }
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+
+    pub fn children_mut(&mut self) -> &mut Vec<Node> {
+        &mut self.children
+    }
+    pub fn add_class<T: ToString>(&mut self, class: T) {
+        let class = class.to_string();
+        if !self.classes.contains(&class) {
+            self.classes.push(class);
+        }
+    }
+    pub fn remove_class(&mut self, class: &str) {
+        self.classes.retain(|c| c != class);
+    }
+    pub fn set_attr<N: ToString, V: ToString>(&mut self, name: N, value: V) {
+        self.put_attr(name.to_string(),Some(value.to_string()));
+    }
+    pub fn remove_attr(&mut self, name: &str) {
+        self.attrs.retain(|(n,_)| n != name);
+    }
+
+    // depth first, the element itself included
+    pub fn walk<V: Visitor + ?Sized>(&self, v: &mut V) {
+        if !v.element(self) { return; }
+        for c in &self.children {
+            match c {
+                Node::Text(t) => v.text(t),
+                Node::Raw(r) => v.raw(r),
+                Node::Element(e) => e.walk(v),
+            }
+        }
+    }
+    pub fn walk_mut<V: VisitorMut + ?Sized>(&mut self, v: &mut V) {
+        if !v.element(self) { return; }
+        for c in &mut self.children {
+            if let Node::Element(e) = c {
+                e.walk_mut(v);
+            }
+        }
+    }
+    pub fn find_by_id(&self, id: &str) -> Option<&Element> {
+        if self.id.as_deref() == Some(id) { return Some(self); }
+        self.children.iter().find_map(|c| match c {
+            Node::Element(e) => e.find_by_id(id),
+            _ => None,
+        })
+    }
+    pub fn find_by_id_mut(&mut self, id: &str) -> Option<&mut Element> {
+        if self.id.as_deref() == Some(id) { return Some(self); }
+        self.children.iter_mut().find_map(|c| match c {
+            Node::Element(e) => e.find_by_id_mut(id),
+            _ => None,
+        })
+    }
+    pub fn find_all_by_class(&self, class: &str) -> Vec<&Element> {
+        let mut res = Vec::new();
+        self.collect_by_class(class,&mut res);
+        res
+    }
+    fn collect_by_class<'a>(&'a self, class: &str, res: &mut Vec<&'a Element>) {
+        if self.has_class(class) { res.push(self); }
+        for c in &self.children {
+            if let Node::Element(e) = c {
+                e.collect_by_class(class,res);
+            }
+        }
+    }
+}
+
+// returning false from element skips its children
+pub trait Visitor {
+    fn element(&mut self, _e: &Element) -> bool { true }
+    fn text(&mut self, _t: &str) {}
+    fn raw(&mut self, _r: &str) {}
+}
+
+pub trait VisitorMut {
+    fn element(&mut self, e: &mut Element) -> bool;
+}
+impl<F: FnMut(&mut Element)> VisitorMut for F {
+    fn element(&mut self, e: &mut Element) -> bool {
+        self(e);
+        true
+    }
+}
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_scoped(f,None)
+    }
+}
+impl Element {
+    // every class gets the prefix of the scope, except global ones
+    pub(crate) fn write_scoped(&self, f: &mut dyn fmt::Write, scope: Option<&Scope>) -> fmt::Result {
+        f.write_str("<")?;
+        write_name(f,&self.tag)?;
+        if let Some(id) = &self.id {
+            f.write_str(" id='")?;
+            write_escaped(f,id)?;
+            f.write_str("'")?;
+        }
+        if !self.classes.is_empty() {
+            f.write_str(" class='")?;
+            for (i,c) in self.classes.iter().flat_map(|c| c.split_whitespace()).enumerate() {
+                if i > 0 { f.write_str(" ")?; }
+                match scope {
+                    None => write_escaped(f,c)?,
+                    Some(scope) => write_escaped(f,&scope.class(c))?,
+                }
+            }
+            f.write_str("'")?;
+        }
+        for (n,v) in &self.attrs {
+            f.write_str(" ")?;
+            write_name(f,n)?;
+            if let Some(v) = v {
+                f.write_str("='")?;
+                write_escaped(f,v)?;
+                f.write_str("'")?;
+            }
+        }
+        if !self.style.is_empty() {
+            f.write_str(" style='")?;
+            for (i,(p,v)) in self.style.iter().enumerate() {
+                if i > 0 { f.write_str(" ")?; }
+                write_escaped(f,p)?;
+                f.write_str(": ")?;
+                write_escaped(f,v)?;
+                f.write_str(";")?;
+            }
+            f.write_str("'")?;
+        }
+        f.write_str(">")?;
+        if self.is_void() {
+            return Ok(());
+        }
+        for c in &self.children {
+            c.write_scoped(f,scope)?;
+        }
+        f.write_str("</")?;
+        write_name(f,&self.tag)?;
+        f.write_str(">")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn element() {
+        let e = Element::new("ul").class("list").class("wide").class("list")
+            .child(Element::new("li").child(Element::new("a").attr("href","/q?a=1&b='2'").text("<next>")))
+            .child(Element::new("li").raw("<b>x</b>").child(Element::new("br")).text(" y"))
+            .child(Element::new("input").attr("type","checkbox").flag("checked").attr("type","radio").text("ignored"));
+        assert_eq!(e.to_string(),"<ul class='list wide'>\
+<li><a href='/q?a=1&amp;b=&#39;2&#39;'>&lt;next&gt;</a></li>\
+<li><b>x</b><br> y</li>\
+<input type='radio' checked>\
+</ul>");
+        assert_eq!(Element::new("pre").id("o\"ut").to_string(),"<pre id='o&quot;ut'></pre>");
+    }
+
+    #[test]
+    fn attributes() {
+        let e = Element::new("div").classes_from(vec!["row".to_string(),format!("status_{}","fail'ed")])
+            .data("key","a'b").data("key","7").on("mouseover","hl(this, \"x\")")
+            .style("color","red").style("width","10px").style("color","blue")
+            .attr("x onclick='evil()'","1");
+        assert_eq!(e.to_string(),"<div class='row status_fail&#39;ed' data-key='7' onmouseover='hl(this, &quot;x&quot;)' \
+xonclickevil()='1' style='color: blue; width: 10px;'></div>");
+        assert_eq!(Element::new("p><script>alert(1)</script").text("x").to_string(),"<pscriptalert(1)script>x</pscriptalert(1)script>");
+    }
+
+    #[test]
+    fn query() {
+        struct Texts(Vec<String>);
+        impl Visitor for Texts {
+            fn element(&mut self, e: &Element) -> bool { !e.has_class("skip") }
+            fn text(&mut self, t: &str) { self.0.push(t.to_string()); }
+        }
+        let mut e = Element::new("div").id("page")
+            .child(Element::new("div").class("row").class("error").text("a"))
+            .child(Element::new("div").class("row").class("skip").text("b")
+                   .child(Element::new("div").id("inner").class("error").text("c")));
+        assert_eq!(e.find_all_by_class("error").len(),2);
+        assert_eq!(e.find_by_id("inner").map(|e| e.classes().to_vec()),Some(vec!["error".to_string()]));
+        assert!(e.find_by_id("none").is_none());
+
+        let mut texts = Texts(Vec::new());
+        e.walk(&mut texts);
+        assert_eq!(texts.0,vec!["a"]);
+
+        e.walk_mut(&mut |e: &mut Element| if e.has_class("error") { e.add_class("highlight") });
+        e.find_by_id_mut("inner").unwrap().remove_class("error");
+        e.find_by_id_mut("page").unwrap().children_mut().insert(0,Element::new("p").text("banner").into());
+        assert_eq!(e.to_string(),"<div id='page'><p>banner</p><div class='row error highlight'>a</div>\
+<div class='row skip'>b<div id='inner' class='highlight'>c</div></div></div>");
+    }
+}
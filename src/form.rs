@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum FormError {
+    BadEncoding(String),
+    MissingField(String),
+    BadValue {
+        field: String,
+        value: String,
+    },
+}
+
+fn decode(s: &str) -> Result<String,FormError> {
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => res.push(b' '),
+            b'%' => {
+                let hex = match bytes.get(i+1 .. i+3) {
+                    Some(h) => std::str::from_utf8(h).ok().and_then(|h| u8::from_str_radix(h,16).ok()),
+                    None => None,
+                };
+                match hex {
+                    Some(b) => res.push(b),
+                    None => return Err(FormError::BadEncoding(s.to_string())),
+                }
+                i += 2;
+            },
+            b => res.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(res).map_err(|_| FormError::BadEncoding(s.to_string()))
+}
+
+pub(crate) fn encode(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A' ..= b'Z' | b'a' ..= b'z' | b'0' ..= b'9' | b'-' | b'_' | b'.' | b'~' => res.push(b as char),
+            b' ' => res.push('+'),
+            b => res += &format!("%{:02X}",b),
+        }
+    }
+    res
+}
+
+pub fn parse_urlencoded(body: &str) -> Result<Vec<(String,String)>,FormError> {
+    let mut res = Vec::new();
+    for pair in body.trim().split('&') {
+        if pair.is_empty() { continue; }
+        let (k,v) = match pair.find('=') {
+            Some(i) => (&pair[..i],&pair[i+1..]),
+            None => (pair,""),
+        };
+        res.push((decode(k)?,decode(v)?));
+    }
+    Ok(res)
+}
+
+fn parse_value<K: FromStr>(field: &str, value: String) -> Result<K,FormError> {
+    value.parse().map_err(|_| FormError::BadValue { field: field.to_string(), value })
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct BulkAction<K> {
+    pub action: String,
+    pub keys: Vec<K>,
+}
+impl<K: FromStr> BulkAction<K> {
+    pub fn parse(body: &str, name: &str) -> Result<BulkAction<K>,FormError> {
+        let mut action = None;
+        let mut keys = Vec::new();
+        for (k,v) in parse_urlencoded(body)? {
+            if k == name {
+                keys.push(parse_value(name,v)?);
+            } else if k == "action" {
+                action = Some(v);
+            }
+        }
+        match action {
+            Some(action) => Ok(BulkAction { action, keys }),
+            None => Err(FormError::MissingField("action".to_string())),
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct CellEdit<K> {
+    pub key: K,
+    pub field: String,
+    pub value: String,
+}
+impl<K: FromStr> CellEdit<K> {
+    pub fn parse(body: &str) -> Result<CellEdit<K>,FormError> {
+        let mut key = None;
+        let mut field = None;
+        let mut value = None;
+        for (k,v) in parse_urlencoded(body)? {
+            match k.as_str() {
+                "key" => key = Some(v),
+                "field" => field = Some(v),
+                "value" => value = Some(v),
+                _ => {},
+            }
+        }
+        match (key,field,value) {
+            (Some(key),Some(field),Some(value)) => Ok(CellEdit { key: parse_value("key",key)?, field, value }),
+            (None,_,_) => Err(FormError::MissingField("key".to_string())),
+            (_,None,_) => Err(FormError::MissingField("field".to_string())),
+            (_,_,None) => Err(FormError::MissingField("value".to_string())),
+        }
+    }
+    pub fn value_as<T: FromStr>(&self) -> Result<T,FormError> {
+        parse_value(&self.field,self.value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bulk_action() {
+        let b = BulkAction::<u64>::parse("keys=17&keys=42&action=retry+now","keys").unwrap();
+        assert_eq!(b,BulkAction { action: "retry now".to_string(), keys: vec![17,42] });
+
+        let b = BulkAction::<String>::parse("action=cancel&keys=job%2F%C3%A9","keys").unwrap();
+        assert_eq!(b.keys,vec!["job/é".to_string()]);
+
+        assert!(matches!(BulkAction::<u64>::parse("keys=x&action=cancel","keys"),Err(FormError::BadValue{ .. })));
+        assert!(matches!(BulkAction::<u64>::parse("keys=1","keys"),Err(FormError::MissingField(_))));
+        assert!(matches!(BulkAction::<u64>::parse("keys=%4","keys"),Err(FormError::BadEncoding(_))));
+    }
+
+    #[test]
+    fn cell_edit() {
+        assert_eq!(decode(&encode("a b&c=é/%")).unwrap(),"a b&c=é/%");
+        let e = CellEdit::<String>::parse("key=tenant%3A7&field=quota&value=1500").unwrap();
+        assert_eq!((e.key.as_str(),e.field.as_str()),("tenant:7","quota"));
+        assert_eq!(e.value_as::<u32>().unwrap(),1500);
+        assert!(matches!(e.value_as::<bool>(),Err(FormError::BadValue{ .. })));
+        assert!(matches!(CellEdit::<u64>::parse("key=1&value=2"),Err(FormError::MissingField(f)) if f == "field"));
+    }
+}
@@ -0,0 +1,257 @@
+use std::{fmt, io};
+
+use super::{
+    Style,Block,Element,Node,Component,Dependency,Scope,Csp,
+    TableBuilder,TableDrawer,IoFmt,
+    csp::HandlerWriter,
+};
+
+// Swaps the content of a target with html fetched from a url:
+//   data-swap-url='/panel' data-swap-target='#jobs'      on click
+//   <form data-swap-target='#jobs'>                      on submit, to data-swap-url or the form action
+//   data-swap-url='/panel' data-swap-every='5000'        every 5s while the page is visible
+// The target is the element itself without data-swap-target. Styles and scripts of the fragment
+// get the page nonce, so fragments also work on pages with a Content-Security-Policy.
+const SWAP_JS: &str = "function uiSwap(target, url, opts) {
+    var t = (typeof target === 'string') ? document.querySelector(target) : target;
+    if (!t || !url) { return; }
+    opts = Object.assign({}, opts);
+    opts.headers = new Headers(opts.headers);
+    opts.headers.set('X-Fragment', '1');
+    t.setAttribute('aria-busy', 'true');
+    fetch(url, opts).then(function(r) {
+        if (!r.ok) { throw new Error(r.status + ' ' + r.statusText); }
+        return r.text();
+    }).then(function(html) {
+        var tpl = document.createElement('template');
+        tpl.innerHTML = html;
+        var n = document.querySelector('style[nonce],script[nonce]');
+        if (n) { tpl.content.querySelectorAll('style').forEach(function(s) { s.nonce = n.nonce; }); }
+        t.replaceChildren(tpl.content);
+        // scripts inserted as html do not run
+        t.querySelectorAll('script').forEach(function(s) {
+            var r = document.createElement('script');
+            if (n) { r.nonce = n.nonce; }
+            r.textContent = s.textContent;
+            s.replaceWith(r);
+        });
+        t.removeAttribute('data-swap-error');
+        swapTimers(t);
+    }).catch(function(err) {
+        t.setAttribute('data-swap-error', err);
+    }).finally(function() {
+        t.removeAttribute('aria-busy');
+    });
+}
+function swapTarget(el) {
+    var sel = el.getAttribute('data-swap-target');
+    return sel ? document.querySelector(sel) : el;
+}
+function swapTimers(root) {
+    root.querySelectorAll('[data-swap-every]').forEach(function(el) {
+        var ms = parseInt(el.getAttribute('data-swap-every'), 10);
+        if (el.uiSwapTimer || !(ms > 0)) { return; }
+        el.uiSwapTimer = setInterval(function() {
+            if (!el.isConnected) { clearInterval(el.uiSwapTimer); return; }
+            if (!document.hidden) { uiSwap(swapTarget(el), el.getAttribute('data-swap-url')); }
+        }, ms);
+    });
+}
+document.addEventListener('click', function(e) {
+    var el = e.target.closest('[data-swap-url]:not(form):not([data-swap-every])');
+    if (!el) { return; }
+    e.preventDefault();
+    uiSwap(swapTarget(el), el.getAttribute('data-swap-url'));
+});
+document.addEventListener('submit', function(e) {
+    var f = e.target;
+    if (!f.hasAttribute('data-swap-target')) { return; }
+    e.preventDefault();
+    var data = new URLSearchParams(new FormData(f));
+    var url = f.getAttribute('data-swap-url') || f.action;
+    if ((f.getAttribute('method') || 'get').toLowerCase() === 'post') {
+        uiSwap(swapTarget(f), url, { method: 'POST', body: data });
+    } else {
+        uiSwap(swapTarget(f), url + ((url.indexOf('?') < 0) ? '?' : '&') + data);
+    }
+});
+document.addEventListener('DOMContentLoaded', function() { swapTimers(document); });
+";
+
+// Part of a page for a partial update: the markup with just the styles it needs.
+// Component scripts are left out, the page includes the component dependencies.
+#[derive(Debug,Default)]
+pub struct Fragment {
+    css: String,
+    styles: Vec<Style>,
+    body: Vec<Node>,
+    tables: TableDrawer,
+    dependencies: Vec<String>,
+    scope: Option<Scope>,
+    csp: Option<Csp>,
+}
+impl fmt::Display for Fragment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let nonce = self.csp.as_ref().map(|c| format!(" nonce='{}'",c.nonce())).unwrap_or_default();
+        let css = self.css();
+        if !css.is_empty() {
+            write!(f,"<style{}>\n{}\n</style>\n",nonce,css)?;
+        }
+        match &self.csp {
+            None => self.write_body(f),
+            Some(csp) => {
+                // the ids must not clash with the ones of the page or of other fragments
+                let mut w = HandlerWriter::new(f).with_prefix(&format!("f{}",csp.nonce()));
+                self.write_body(&mut w)?;
+                let handlers = w.finish()?;
+                match handlers.is_empty() {
+                    true => Ok(()),
+                    false => write!(f,"<script{}>\n{}</script>\n",nonce,handlers),
+                }
+            },
+        }
+    }
+}
+impl Fragment {
+    // sent by the swap script with every request
+    pub const HEADER: &'static str = "X-Fragment";
+
+    pub fn new() -> Fragment {
+        Fragment::default()
+    }
+    // the swap script, for the page the fragments go to
+    pub fn dependency() -> Dependency {
+        Dependency::new("fragments").js(SWAP_JS)
+    }
+    fn write_body(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        for n in &self.body {
+            n.write_scoped(w,self.scope.as_ref())?;
+            w.write_str("\n")?;
+        }
+        Ok(())
+    }
+
+    // the scope of the page
+    pub fn with_scope(mut self, scope: Scope) -> Fragment {
+        self.scope = Some(scope);
+        self
+    }
+    // inline on* handlers are delegated from a nonce'd script, which the swap script runs;
+    // a fresh Csp per response is enough, the swap script gives the page nonce to the fragment
+    pub fn with_csp(mut self, csp: Csp) -> Fragment {
+        self.csp = Some(csp);
+        self
+    }
+    pub fn with_styles(mut self, css: &str) -> Fragment {
+        if !self.css.is_empty() { self.css += "\n"; }
+        self.css += css;
+        self
+    }
+    pub fn push_style(&mut self, s: Style) {
+        self.styles.push(s);
+    }
+    pub fn push_block(&mut self, b: Block) {
+        self.body.push(b.into());
+    }
+    pub fn push_element(&mut self, e: Element) {
+        self.body.push(e.into());
+    }
+    // markup of TableBuilder::row and the like
+    pub fn push_raw<T: ToString>(&mut self, html: T) {
+        self.body.push(Node::Raw(html.to_string()));
+    }
+    pub fn push_component<C: Component + ?Sized>(&mut self, c: &C) {
+        self.add_dependencies(c.dependencies());
+        self.push_element(c.render());
+    }
+    // only the css is kept
+    pub fn add_dependencies(&mut self, deps: Vec<Dependency>) {
+        for dep in deps {
+            if self.dependencies.contains(&dep.id) { continue; }
+            if !dep.css.is_empty() {
+                if !self.css.is_empty() { self.css += "\n"; }
+                self.css += &dep.css;
+            }
+            self.dependencies.push(dep.id);
+        }
+    }
+    // rows drawn with this drawer, their styles are added by add_tables
+    pub fn drawer(&mut self) -> &mut TableDrawer {
+        &mut self.tables
+    }
+    pub fn add_tables(&mut self, tb: &TableBuilder) {
+        let deps = tb.dependencies(&self.tables);
+        self.add_dependencies(deps);
+    }
+    pub fn css(&self) -> String {
+        let mut css = self.css.clone();
+        for s in &self.styles {
+            if !css.is_empty() { css += "\n"; }
+            css += &s.to_string();
+        }
+        match &self.scope {
+            None => css,
+            Some(scope) => scope.css(&css),
+        }
+    }
+    pub fn write_to<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        let mut w = IoFmt::new(w);
+        match fmt::Write::write_fmt(&mut w,format_args!("{}",self)) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(w.error.take().unwrap_or_else(|| io::Error::other("formatting error"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Tabs,Tab,HtmlProducer,RowOptions};
+
+    #[test]
+    fn fragments() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("jobs",300).unwrap();
+        let r0 = tb.create_row_fixed(table,&[None,Some(60)]).unwrap();
+        let r1 = tb.create_row_fixed(table,&[Some(100),None]).unwrap();
+
+        let mut frag = Fragment::new();
+        let row = tb.row(r1,"job",vec!["a".to_string(),"b".to_string()],frag.drawer()).unwrap();
+        frag.push_raw(row);
+        frag.add_tables(&tb);
+        let s = frag.to_string();
+        assert!(s.starts_with("<style>\n.jobs_r1_c0"));
+        assert!(!s.contains(".jobs_r0_c0"));
+        assert!(s.ends_with("</style>\n<div class='job'>\n<div class='jobs_r1_c0'>a</div>\n<div class='jobs_r1_c1'>b</div>\n</div>\n"));
+
+        let mut view = tb.view();
+        view.row(r0,"job",&RowOptions::new().href("/j/1"),&["c","d"]).unwrap();
+        let mut page = HtmlProducer::default();
+        let tabs = Tabs::new(&mut page,vec![Tab { name: "All".to_string(), count: 1, active: false, href: "/".to_string() }]);
+        let mut frag = Fragment::new().with_scope(Scope::new("ui"));
+        frag.push_component(&tabs);
+        frag.push_component(&view);
+        frag.push_block(Block::new("note").text("updated"));
+        let s = frag.to_string();
+        assert!(s.contains(".ui-tab_row { display: flex;"));
+        assert!(s.contains(".ui-jobs_r0_c0 {"));
+        assert!(!s.contains(".ui-jobs_r1_c0 {"));
+        assert!(!s.contains("tabClicked"));
+        assert!(s.contains("<div class='ui-tabs'>"));
+        assert!(s.ends_with("<div class='ui-note'>updated</div>\n"));
+
+        let mut frag = Fragment::new();
+        frag.push_block(Block::new("panel").text("x"));
+        assert_eq!(frag.to_string(),"<div class='panel'>x</div>\n");
+        assert!(Fragment::dependency().js.contains("function uiSwap("));
+
+        let mut frag = Fragment::new().with_csp(Csp::new().with_nonce("n0nce"));
+        frag.push_style(Style::new("panel").opt("color","red"));
+        frag.push_element(Element::new("button").attr("onclick","save(1)").text("Save"));
+        let s = frag.to_string();
+        assert!(s.starts_with("<style nonce='n0nce'>\n.panel {"));
+        assert!(s.contains("<button data-ui-onclick='fn0nce_0'>Save</button>\n<script nonce='n0nce'>\n"));
+        assert!(s.contains("function(event) { save(1)\n"));
+        assert!(s.contains("all['fn0nce_'] = [\n"));
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use super::css::{parse_items,CssItem};
+
+// Horizontal box model of generated rows: floats, margins, paddings, widths and min-widths,
+// in the subset of css the table generator emits. All boxes are content-box sized.
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum LayoutError {
+    BadValue {
+        class: String,
+        property: String,
+        value: String,
+    },
+    UnbalancedDivs,
+    UnterminatedTag {
+        tag: String,
+    },
+    // empty, unquoted or unclosed class attribute
+    BadClass {
+        tag: String,
+    },
+    Overflow {
+        class: String,
+        x: usize,
+        width: usize,
+        container: (usize,usize),
+    },
+    Overlap {
+        first: String,
+        second: String,
+    },
+    MinWidth {
+        class: String,
+        min_width: usize,
+        width: usize,
+    },
+    Gap {
+        container: String,
+        line: usize,
+        covered: usize,
+        width: usize,
+    },
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct LayoutBox {
+    pub class: String,
+    pub depth: usize,
+    pub line: usize,
+    pub x: usize,
+    // border box width
+    pub width: usize,
+    pub content_width: usize,
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Layout {
+    pub boxes: Vec<LayoutBox>,
+}
+impl Layout {
+    pub fn get(&self, class: &str) -> Option<&LayoutBox> {
+        self.boxes.iter().find(|b| b.class == class)
+    }
+}
+
+#[derive(Debug,Default)]
+struct Props {
+    padding_left: usize,
+    padding_right: usize,
+    margin_left: usize,
+    margin_right: usize,
+    width: Option<usize>,
+    min_width: usize,
+    float: Option<bool>, // Some(true) is left
+}
+
+#[derive(Debug)]
+struct Node {
+    class: String,
+    classes: Vec<String>,
+    children: Vec<Node>,
+}
+
+fn px(class: &str, property: &str, value: &str) -> Result<usize,LayoutError> {
+    let v = value.trim();
+    let v = match v == "0" {
+        true => "0px",
+        false => v,
+    };
+    v.strip_suffix("px").and_then(|n| n.trim().parse().ok()).ok_or_else(|| LayoutError::BadValue {
+        class: class.to_string(),
+        property: property.to_string(),
+        value: value.to_string(),
+    })
+}
+
+fn sides(class: &str, property: &str, value: &str) -> Result<(usize,usize),LayoutError> {
+    let v = value.split_whitespace().collect::<Vec<_>>();
+    match v.len() {
+        1 ..= 3 => {
+            let lr = match v.len() { 1 => v[0], _ => v[1] };
+            Ok((px(class,property,lr)?,px(class,property,lr)?))
+        },
+        _ => Ok((px(class,property,v[3])?,px(class,property,v[1])?)),
+    }
+}
+
+fn apply(props: &mut Props, class: &str, property: &str, value: &str) -> Result<(),LayoutError> {
+    match property {
+        "padding" => { let (l,r) = sides(class,property,value)?; props.padding_left = l; props.padding_right = r; },
+        "padding-left" => props.padding_left = px(class,property,value)?,
+        "padding-right" => props.padding_right = px(class,property,value)?,
+        "margin" => { let (l,r) = sides(class,property,value)?; props.margin_left = l; props.margin_right = r; },
+        "margin-left" => props.margin_left = px(class,property,value)?,
+        "margin-right" => props.margin_right = px(class,property,value)?,
+        "width" => props.width = match value { "auto" => None, _ => Some(px(class,property,value)?) },
+        "min-width" => props.min_width = px(class,property,value)?,
+        "float" => props.float = match value {
+            "left" => Some(true),
+            "right" => Some(false),
+            _ => None,
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+fn parse_divs(html: &str) -> Result<Vec<Node>,LayoutError> {
+    let mut stack: Vec<Node> = vec![Node { class: String::new(), classes: Vec::new(), children: Vec::new() }];
+    let mut rest = html;
+    while let Some(i) = rest.find('<') {
+        rest = &rest[i+1..];
+        let end = rest.find('>').ok_or_else(|| LayoutError::UnterminatedTag { tag: rest.to_string() })?;
+        let tag = &rest[..end];
+        let is_div = tag.strip_prefix("div").map(|t| t.is_empty() || t.starts_with(|c: char| c.is_whitespace() || c == '/')).unwrap_or(false);
+        if is_div {
+            let classes = match tag.find("class=") {
+                Some(c) => {
+                    let bad = || LayoutError::BadClass { tag: tag.to_string() };
+                    let mut q = tag[c+6..].chars();
+                    let quote = match q.next() {
+                        Some(c @ ('\'' | '"')) => c,
+                        _ => return Err(bad()),
+                    };
+                    let value = q.as_str();
+                    let value = value.find(quote).map(|e| &value[..e]).ok_or_else(bad)?;
+                    let classes = value.split_whitespace().map(|c| c.to_string()).collect::<Vec<_>>();
+                    if classes.is_empty() { return Err(bad()); }
+                    classes
+                },
+                None => Vec::new(),
+            };
+            stack.push(Node {
+                class: classes.first().cloned().unwrap_or_default(),
+                classes,
+                children: Vec::new(),
+            });
+        } else if tag == "/div" {
+            let node = stack.pop().ok_or(LayoutError::UnbalancedDivs)?;
+            stack.last_mut().ok_or(LayoutError::UnbalancedDivs)?.children.push(node);
+        }
+        rest = &rest[end+1..];
+    }
+    match stack.len() {
+        1 => Ok(stack.pop().map(|r| r.children).unwrap_or_default()),
+        _ => Err(LayoutError::UnbalancedDivs),
+    }
+}
+
+struct Evaluator {
+    rules: Vec<(String,Vec<(String,String)>)>,
+    boxes: Vec<LayoutBox>,
+}
+impl Evaluator {
+    fn props(&self, node: &Node) -> Result<Props,LayoutError> {
+        let mut props = Props::default();
+        for (sel,decls) in &self.rules {
+            if node.classes.iter().any(|c| *c == sel[1..]) {
+                for (p,v) in decls {
+                    apply(&mut props,&node.class,p,v)?;
+                }
+            }
+        }
+        Ok(props)
+    }
+    fn container(&mut self, name: &str, children: &[Node], x0: usize, width: usize, depth: usize) -> Result<(),LayoutError> {
+        let mut line = 0;
+        let mut left = x0;
+        let mut right = x0 + width;
+        // border boxes on the current line
+        let mut placed: Vec<(usize,usize,String)> = Vec::new();
+        let mut lines = BTreeMap::new();
+        let mut finish_line = |line: usize, placed: &mut Vec<(usize,usize,String)>| {
+            let covered = placed.iter().fold(0,|acc,(a,b,_)| acc + (b - a));
+            lines.insert(line,covered);
+            placed.clear();
+        };
+        for node in children {
+            let props = self.props(node)?;
+            let pads = props.padding_left + props.padding_right;
+            let (x,content) = match props.float {
+                Some(is_left) => {
+                    let content = props.width.unwrap_or(props.min_width);
+                    let outer = props.margin_left + pads + content + props.margin_right;
+                    if (left + outer > right) && !placed.is_empty() {
+                        finish_line(line,&mut placed);
+                        line += 1;
+                        left = x0;
+                        right = x0 + width;
+                    }
+                    match is_left {
+                        true => { left += outer; (left - outer + props.margin_left,content) },
+                        false => { right = right.saturating_sub(outer); (right + props.margin_left,content) },
+                    }
+                },
+                None => {
+                    let x = x0 + props.margin_left;
+                    let content = match props.width {
+                        Some(w) => w,
+                        None => width.saturating_sub(props.margin_left + props.margin_right + pads),
+                    };
+                    (x,content)
+                },
+            };
+            if content < props.min_width {
+                return Err(LayoutError::MinWidth { class: node.class.clone(), min_width: props.min_width, width: content });
+            }
+            let bw = pads + content;
+            if (x < x0) || (x + bw > x0 + width) {
+                return Err(LayoutError::Overflow { class: node.class.clone(), x, width: bw, container: (x0,width) });
+            }
+            for (a,b,cls) in &placed {
+                if (x < *b) && (*a < x + bw) {
+                    return Err(LayoutError::Overlap { first: cls.clone(), second: node.class.clone() });
+                }
+            }
+            placed.push((x,x + bw,node.class.clone()));
+            self.boxes.push(LayoutBox { class: node.class.clone(), depth, line, x, width: bw, content_width: content });
+            self.container(&node.class,&node.children,x + props.padding_left,content,depth + 1)?;
+            if props.float.is_none() {
+                finish_line(line,&mut placed);
+                line += 1;
+                left = x0;
+                right = x0 + width;
+            }
+        }
+        if !placed.is_empty() { finish_line(line,&mut placed); }
+        for (line,covered) in lines {
+            if covered != width {
+                return Err(LayoutError::Gap { container: name.to_string(), line, covered, width });
+            }
+        }
+        Ok(())
+    }
+}
+
+// Lays out the divs of html (as produced by TableBuilder::row) in a container of the given width
+// and checks that every line of every container is filled exactly, without overlaps or overflows.
+pub fn evaluate(styles: &str, html: &str, width: usize) -> Result<Layout,LayoutError> {
+    let mut rules = Vec::new();
+    for item in parse_items(styles) {
+        if let CssItem::Rule { selectors, decls } = item {
+            for sel in selectors {
+                if sel.starts_with('.') && !sel[1..].contains(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')) {
+                    rules.push((sel,decls.clone()));
+                }
+            }
+        }
+    }
+    let nodes = parse_divs(html)?;
+    let mut ev = Evaluator { rules, boxes: Vec::new() };
+    ev.container("",&nodes,0,width,0)?;
+    Ok(Layout { boxes: ev.boxes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors() {
+        let html = "<div class='a'>x</div><div class='b'>y</div>";
+        let l = evaluate(".a { width: 40px; padding: 0 5px; float: left; } .b { width: 50px; float: right; }",html,100).unwrap();
+        assert_eq!((l.get("a").unwrap().x,l.get("a").unwrap().width),(0,50));
+        assert_eq!(l.get("b").unwrap().x,50);
+        assert_eq!(evaluate(".a { width: 40px; float: left; } .b { width: 50px; float: right; }",html,100),
+                   Err(LayoutError::Gap { container: String::new(), line: 0, covered: 90, width: 100 }));
+        assert_eq!(evaluate(".a { width: 60px; float: left; } .b { min-width: 50px; }",html,100),
+                   Err(LayoutError::Overlap { first: "a".to_string(), second: "b".to_string() }));
+        assert_eq!(evaluate(".a { width: 60px; float: left; } .b { min-width: 50px; margin-left: 60px; }",html,100),
+                   Err(LayoutError::MinWidth { class: "b".to_string(), min_width: 50, width: 40 }));
+        assert!(matches!(evaluate(".a { width: 10em; }",html,100),Err(LayoutError::BadValue{ .. })));
+        assert_eq!(evaluate("",&html[1..],100),Err(LayoutError::UnbalancedDivs));
+    }
+
+    #[test]
+    fn bad_markup() {
+        assert_eq!(evaluate("","<",100),Err(LayoutError::UnterminatedTag { tag: String::new() }));
+        assert_eq!(evaluate("","<é",100),Err(LayoutError::UnterminatedTag { tag: "é".to_string() }));
+        assert_eq!(evaluate("","<div class=>x</div>",100),Err(LayoutError::BadClass { tag: "div class=".to_string() }));
+        assert_eq!(evaluate("","<div class=a>x</div>",100),Err(LayoutError::BadClass { tag: "div class=a".to_string() }));
+        assert_eq!(evaluate("","<div class=''>x</div>",100),Err(LayoutError::BadClass { tag: "div class=''".to_string() }));
+        assert_eq!(evaluate("","<div class='a>x</div>",100),Err(LayoutError::BadClass { tag: "div class='a".to_string() }));
+        assert_eq!(evaluate(".é { width: 100px; }","<p>é</p><div class=\"é\">é</div>",100).map(|l| l.get("é").map(|b| b.width)),Ok(Some(100)));
+    }
+}
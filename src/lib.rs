@@ -1,177 +1,426 @@
+use scope::ScopedWriter;
+use csp::HandlerWriter;
+
+use std::{
+    fmt, io,
+    borrow::Cow,
+    sync::Arc,
+};
+
+#[macro_use]
+mod macros;
+
 pub mod resources;
+pub mod layout;
 
 mod tabs;
 mod table;
+mod form;
+mod css;
+mod element;
+mod component;
+mod stylesheet;
+mod theme;
+mod defaults;
+mod scope;
+mod markup;
+mod document;
+mod assets;
+mod csp;
+mod fragment;
 
 pub use tabs::{Tab,Tabs};
 
-pub use table::{TableBuilder,TableDrawer,RowRef,TableError,SoftColumn};
+pub use element::{Element,Node,Visitor,VisitorMut};
+
+pub use component::{Component,Dependency};
+
+pub use defaults::DefaultStyles;
+
+pub use scope::Scope;
+
+pub use document::{Document,Favicon};
+
+pub use assets::{AssetRegistry,AssetResponse,Script,ScriptLoad};
+
+pub use csp::Csp;
+
+pub use fragment::Fragment;
+
+pub use theme::{Theme,Themes,ThemeToggle,Palette,ColorScheme,Badge,BadgeKind};
 
-#[derive(Debug)]
+pub use table::{TableBuilder,TableDrawer,RowRef,TableRef,TableError,SoftColumn,RowOptions,RowDetail,Selection,EditableCell,EditKind,Overflow,TableView,RowGeometry,BoxGeometry,BoxWidth,Side,ClassNaming};
+
+pub use css::optimize_css;
+
+pub use stylesheet::{Stylesheet,Rule,Media,Keyframes,Selector,Pseudo,Nth,AttrOp,Value,Length,Unit};
+
+pub use form::{FormError,BulkAction,CellEdit,parse_urlencoded};
+
+#[derive(Debug,Clone)]
 pub struct Style {
-    name: &'static str,
-    opts: Vec<(&'static str, &'static str)>,
+    name: Cow<'static,str>,
+    opts: Vec<(Cow<'static,str>,Cow<'static,str>)>,
 }
 impl Style {
-    pub fn new(style: &'static str) -> Style {
+    pub fn new<S: Into<Cow<'static,str>>>(style: S) -> Style {
         Style {
-            name: style,
+            name: style.into(),
             opts: Vec::new(),
         }
     }
-    pub fn duplicate(&self,style: &'static str) -> Style {
+    pub fn duplicate<S: Into<Cow<'static,str>>>(&self, style: S) -> Style {
         Style {
-            name: style,
+            name: style.into(),
             opts: self.opts.clone(),
         }
     }
-    pub fn opt(mut self, opt: &'static str, value: &'static str) -> Style {
-        let mut idx = None;
-        for (i,(o,_)) in self.opts.iter().enumerate() {
-            if *o == opt { idx = Some(i); break }
-        }
-        match idx {
+    pub fn opt<O: Into<Cow<'static,str>>, V: Into<Cow<'static,str>>>(mut self, opt: O, value: V) -> Style {
+        let (opt,value) = (opt.into(),value.into());
+        match self.opts.iter().position(|(o,_)| *o == opt) {
             Some(i) => self.opts[i] = (opt,value),
             None => self.opts.push((opt,value)),
         }
         self
     }
 }
-impl ToString for Style {
-    fn to_string(&self) -> String {
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.opts.len() {
-            0 => String::new(),
+            0 => Ok(()),
             _ => {
-                let mut s = format!(".{} {{",self.name);
+                write!(f,".{} {{",self.name)?;
                 for (o,v) in &self.opts {
-                    s += "    ";
-                    s += o;
-                    s += ": ";
-                    s += v;
-                    s += ";\n"
+                    writeln!(f,"    {}: {};",o,v)?;
                 }
-                s += "}";
-                s
+                write!(f,"}}")
             },
         }
     }
 }
 
-pub fn classed<T: ToString>(class: &'static str, t: T) -> String {
-    format!("<span class='{}'>{}</span>",class,t.to_string())
+// 64-bit FNV-1a: asset versions and hashed class names, stable between runs and builds
+pub(crate) fn fnv64(data: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in data {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
 }
 
-#[derive(Debug)]
+pub(crate) fn write_escaped<W: fmt::Write + ?Sized>(w: &mut W, s: &str) -> fmt::Result {
+    let mut last = 0;
+    for (i,c) in s.char_indices() {
+        let rep = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            '\'' => "&#39;",
+            _ => continue,
+        };
+        w.write_str(&s[last .. i])?;
+        w.write_str(rep)?;
+        last = i + 1;
+    }
+    w.write_str(&s[last ..])
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    write_escaped(&mut res,s).ok();
+    res
+}
+
+// fmt::Write over io::Write, keeps the io error that fmt::Error can't carry
+pub(crate) struct IoFmt<'a, W: io::Write + ?Sized> {
+    pub(crate) inner: &'a mut W,
+    pub(crate) error: Option<io::Error>,
+}
+impl<'a, W: io::Write + ?Sized> IoFmt<'a,W> {
+    pub(crate) fn new(inner: &'a mut W) -> IoFmt<'a,W> {
+        IoFmt { inner, error: None }
+    }
+}
+impl<W: io::Write + ?Sized> fmt::Write for IoFmt<'_,W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+pub fn classed<C: Into<Cow<'static,str>>, T: ToString>(class: C, t: T) -> String {
+    format!("<span class='{}'>{}</span>",class.into(),t.to_string())
+}
+
+// a div with a class, raw text and sub blocks each on its own line
+#[derive(Debug,Clone)]
 pub struct Block {
-    class: &'static str,
-    onclick: Option<String>,
-    id: Option<String>,
-    text: Option<String>,
-    subs: Vec<Block>,
+    el: Element,
+    has_text: bool,
 }
 impl Block {
-    pub fn new(class: &'static str) -> Block {
+    pub fn new<C: Into<Cow<'static,str>>>(class: C) -> Block {
         Block {
-            class,
-            onclick: None,
-            id: None,
-            text: None,
-            subs: Vec::new(),
+            el: Element::new("div").class(class.into()),
+            has_text: false,
         }
     }
     pub fn id<T: ToString>(mut self, id: T) -> Block {
-        self.id = Some(id.to_string());
+        self.el = self.el.id(id);
         self
     }
-    pub fn onclick<T: ToString>(mut self, onclick: T) -> Block {
-        self.onclick = Some(onclick.to_string());
+    pub fn onclick<T: ToString>(self, onclick: T) -> Block {
+        self.on("click",onclick)
+    }
+    pub fn class<T: ToString>(mut self, class: T) -> Block {
+        self.el = self.el.class(class);
+        self
+    }
+    pub fn classes<I: IntoIterator<Item = T>, T: ToString>(mut self, classes: I) -> Block {
+        self.el = self.el.classes_from(classes);
+        self
+    }
+    pub fn attr<N: ToString, V: ToString>(mut self, name: N, value: V) -> Block {
+        self.el = self.el.attr(name,value);
+        self
+    }
+    pub fn data<N: fmt::Display, V: ToString>(mut self, name: N, value: V) -> Block {
+        self.el = self.el.data(name,value);
+        self
+    }
+    pub fn style<P: ToString, V: ToString>(mut self, prop: P, value: V) -> Block {
+        self.el = self.el.style(prop,value);
+        self
+    }
+    pub fn on<E: fmt::Display, H: ToString>(mut self, event: E, handler: H) -> Block {
+        self.el = self.el.on(event,handler);
         self
     }
     pub fn text<T: ToString>(mut self, t: T) -> Block {
-        self.text = Some(t.to_string());
+        let t = Node::Raw(t.to_string());
+        match self.has_text {
+            true => self.el.children_mut()[0] = t,
+            false => self.el.children_mut().insert(0,t),
+        }
+        self.has_text = true;
         self
     }
+    #[allow(clippy::should_implement_trait)]
     pub fn sub(mut self, s: Block) -> Block {
-        self.subs.push(s);
+        self.sub_mut(s);
         self
     }
     pub fn sub_mut(&mut self, s: Block) {
-        self.subs.push(s);
+        self.el.push(s);
+        self.el.push(Node::Raw("\n".to_string()));
     }
 }
-impl ToString for Block {
-    fn to_string(&self) -> String {
-        let t = match &self.text {
-            Some(t) => t,
-            None => "",
-        };
-        let s = match self.subs.len() {
-            0 => String::new(),
-            _ => {
-                let mut s = String::new();
-                for b in &self.subs {
-                    s += &b.to_string();
-                    s += "\n"
-                }
-                s
-            },
-        };
-        match (&self.id, &self.onclick) {
-            (None,None) => format!("<div class='{}'>{}{}</div>",self.class,t,s),
-            (None, Some(oc)) => format!("<div class='{}' onclick='{}'>{}{}</div>",self.class,oc,t,s),
-            (Some(id),None) => format!("<div id='{}' class='{}'>{}{}</div>",id,self.class,t,s),
-            (Some(id), Some(oc)) => format!("<div id='{}' class='{}' onclick='{}'>{}{}</div>",id,self.class,oc,t,s),
-        }
+impl Block {
+    pub fn element(&self) -> &Element {
+        &self.el
+    }
+    pub fn element_mut(&mut self) -> &mut Element {
+        &mut self.el
+    }
+}
+impl From<Block> for Element {
+    fn from(b: Block) -> Element {
+        b.el
+    }
+}
+impl From<Block> for Node {
+    fn from(b: Block) -> Node {
+        Node::Element(b.el)
+    }
+}
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.el.fmt(f)
     }
 }
 
+// items produced while the page is written
+type LazyWrite = Box<dyn FnOnce(&mut dyn fmt::Write) -> fmt::Result + Send>;
+
+enum BodyPart {
+    Element(Element),
+    Lazy(LazyWrite),
+}
 
 #[derive(Debug,Default)]
 pub struct HtmlProducer {
     title: String,
     scripts: Vec<String>,
     styles: Vec<Style>,
-    blocks: Vec<Block>,
+    body: Vec<Element>,
 
     tables: TableDrawer,
     
     css: String,
+    // of table dependencies, optimized as a whole
+    table_css: String,
+    // after the css of the components, so the defaults override them
+    default_css: String,
+    debug_css: String,
     js: String,
+
+    debug_layout: bool,
+    optimize_styles: Option<bool>,
+    dependencies: Vec<String>,
+    scope: Option<Scope>,
+    document: Document,
+    style_links: Vec<String>,
+    script_srcs: Vec<Script>,
+    csp: Option<Csp>,
 }
-impl ToString for HtmlProducer {
-    fn to_string(&self) -> String {
-        let mut style = self.css.clone();
-        style += "\n";
-        for s in &self.styles {
-            style += &s.to_string();
-            style += "\n";
+impl fmt::Display for HtmlProducer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_page(f,Vec::new())
+    }
+}
+impl HtmlProducer {
+    // parts of a PageStream follow the pushed elements
+    fn write_page(&self, f: &mut dyn fmt::Write, mut parts: Vec<BodyPart>) -> fmt::Result {
+        self.document.write_open(f)?;
+        write!(f,"<title>\n{}\n</title>\n",self.title)?;
+        for href in &self.style_links {
+            writeln!(f,"{}",Element::new("link").attr("rel","stylesheet").attr("href",href))?;
         }
-        let mut body = String::new();
-        for b in &self.blocks {
-            body += &b.to_string();
-            body += "\n";
+        let nonce = self.csp.as_ref().map(|c| format!(" nonce='{}'",c.nonce())).unwrap_or_default();
+        writeln!(f,"<style{}>",nonce)?;
+        match &self.scope {
+            None => self.write_styles(f)?,
+            Some(scope) => {
+                let mut css = String::new();
+                self.write_styles(&mut css)?;
+                f.write_str(&scope.css(&css))?;
+            },
         }
-        let mut script = self.js.clone();
-        script += "\n";
+        f.write_str("\n</style>\n")?;
+        for scr in &self.script_srcs {
+            let mut el = scr.element();
+            if let Some(csp) = &self.csp { el = el.attr("nonce",csp.nonce()); }
+            writeln!(f,"{}",el)?;
+        }
+        write!(f,"<script{}>\n{}\n",nonce,self.js)?;
         for scr in &self.scripts {
-            script += scr;
-            script += "\n";
+            writeln!(f,"{}",scr)?;
+        }
+        f.write_str("\n</script>\n</head>\n<body>\n")?;
+        match &self.csp {
+            None => self.write_body(f,&mut parts)?,
+            Some(_) => {
+                let mut w = HandlerWriter::new(f);
+                self.write_body(&mut w,&mut parts)?;
+                let handlers = w.finish()?;
+                if !handlers.is_empty() {
+                    write!(f,"<script{}>\n{}</script>\n",nonce,handlers)?;
+                }
+            },
         }
-        format!("<html>\n<head>\n<title>\n{}\n</title>\n<style>\n{}\n</style>\n<script>\n{}\n</script>\n</head>\n<body>\n{}\n</body>\n</html>\n",self.title,style,script,body)
+        f.write_str("\n</body>\n</html>\n")
     }
-}
-impl HtmlProducer {
+    fn write_styles(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        if !self.css.is_empty() {
+            writeln!(w,"{}",self.css)?;
+        }
+        if !self.table_css.is_empty() {
+            match self.optimize_styles {
+                Some(minify) => writeln!(w,"{}",optimize_css(&self.table_css,minify))?,
+                None => writeln!(w,"{}",self.table_css)?,
+            }
+        }
+        w.write_str(&self.default_css)?;
+        if self.debug_layout {
+            w.write_str("div { outline: 1px dotted rgba(255,0,0,0.5); outline-offset: -1px; }\n")?;
+            w.write_str(&self.debug_css)?;
+        }
+        for s in &self.styles {
+            writeln!(w,"{}",s)?;
+        }
+        Ok(())
+    }
+    // elements get the scope as they are written, lazy parts through a ScopedWriter
+    fn write_body(&self, w: &mut dyn fmt::Write, parts: &mut Vec<BodyPart>) -> fmt::Result {
+        let scope = self.scope.as_ref();
+        for e in &self.body {
+            e.write_scoped(w,scope)?;
+            w.write_str("\n")?;
+        }
+        for part in parts.drain(..) {
+            match (part,scope) {
+                (BodyPart::Element(e),_) => { e.write_scoped(w,scope)?; w.write_str("\n")?; },
+                (BodyPart::Lazy(l),None) => l(w)?,
+                (BodyPart::Lazy(l),Some(scope)) => {
+                    let mut w = ScopedWriter::new(w,scope);
+                    l(&mut w)?;
+                    w.finish()?;
+                },
+            }
+        }
+        Ok(())
+    }
+
     pub fn with_title<T: ToString>(mut self, t: T) -> HtmlProducer {
         self.title = t.to_string();
         self
     }
-    pub fn with_styles(mut self, css: &String) -> HtmlProducer {
-        if self.css != "" { self.css += "\n"; }
+    pub fn with_styles(mut self, css: &str) -> HtmlProducer {
+        if !self.css.is_empty() { self.css += "\n"; }
         self.css += css;        
         self
     }
-    pub fn with_scripts(mut self, js: &String) -> HtmlProducer {
-        if self.js != "" { self.js += "\n"; }
+    pub fn with_stylesheet(self, css: &Stylesheet) -> HtmlProducer {
+        self.with_styles(&css.to_string())
+    }
+    pub fn with_document(mut self, document: Document) -> HtmlProducer {
+        self.document = document;
+        self
+    }
+    // every class of the styles and the body gets the scope prefix, except global ones
+    pub fn with_scope(mut self, scope: Scope) -> HtmlProducer {
+        self.scope = Some(scope);
+        self
+    }
+    // the variables resolve where they are used, so the order of the styles does not matter
+    pub fn with_themes(mut self, themes: &Themes) -> HtmlProducer {
+        self.add_dependencies(vec![themes.dependency()]);
+        self
+    }
+    pub fn with_default_styles(mut self, defaults: &DefaultStyles) -> HtmlProducer {
+        self.default_css = defaults.css();
+        self
+    }
+    // outlines every generated box, table boxes are colored by kind
+    pub fn with_debug_layout(mut self) -> HtmlProducer {
+        self.debug_layout = true;
+        self
+    }
+    // <link rel='stylesheet'> before the inline styles, the scope does not apply to linked css
+    pub fn with_style_link<T: ToString>(mut self, href: T) -> HtmlProducer {
+        let href = href.to_string();
+        if !self.style_links.contains(&href) { self.style_links.push(href); }
+        self
+    }
+    pub fn with_script_src(mut self, script: Script) -> HtmlProducer {
+        if !self.script_srcs.iter().any(|s| s.src() == script.src()) { self.script_srcs.push(script); }
+        self
+    }
+    // nonces for the inline styles and scripts, inline on* handlers are delegated from a nonce'd script;
+    // the server sends csp_header() as Content-Security-Policy
+    pub fn with_csp(mut self, csp: Csp) -> HtmlProducer {
+        self.csp = Some(csp);
+        self
+    }
+    pub fn csp_header(&self) -> Option<String> {
+        self.csp.as_ref().map(Csp::header_value)
+    }
+    pub fn with_scripts(mut self, js: &str) -> HtmlProducer {
+        if !self.js.is_empty() { self.js += "\n"; }
         self.js += js;        
         self
     }
@@ -183,17 +432,451 @@ impl HtmlProducer {
         self.styles.push(s);
     }
     pub fn push_block(&mut self, b: Block) {
-        self.blocks.push(b);
+        self.body.push(b.into());
+    }
+    pub fn push_element(&mut self, e: Element) {
+        self.body.push(e);
+    }
+    // markup goes to the body, each dependency is added to styles and scripts once per page
+    pub fn push_component<C: Component + ?Sized>(&mut self, c: &C) {
+        self.add_dependencies(c.dependencies());
+        self.push_element(c.render());
+    }
+    // table styles (ids "table:..") are kept apart, for with_optimized_styles
+    pub fn add_dependencies(&mut self, deps: Vec<Dependency>) {
+        for dep in deps {
+            if self.dependencies.contains(&dep.id) { continue; }
+            if !dep.css.is_empty() {
+                let css = match dep.id.starts_with("table:") {
+                    true => &mut self.table_css,
+                    false => &mut self.css,
+                };
+                if !css.is_empty() { *css += "\n"; }
+                *css += &dep.css;
+            }
+            self.debug_css += &dep.debug_css;
+            if !dep.js.is_empty() {
+                if !self.js.is_empty() { self.js += "\n"; }
+                self.js += &dep.js;
+            }
+            self.dependencies.push(dep.id);
+        }
+    }
+    pub fn insert_element(&mut self, index: usize, e: Element) {
+        self.body.insert(index.min(self.body.len()),e);
+    }
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.body.iter()
+    }
+    pub fn elements_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+        self.body.iter_mut()
+    }
+    pub fn find_by_id(&self, id: &str) -> Option<&Element> {
+        self.elements().find_map(|e| e.find_by_id(id))
+    }
+    pub fn find_by_id_mut(&mut self, id: &str) -> Option<&mut Element> {
+        self.elements_mut().find_map(|e| e.find_by_id_mut(id))
+    }
+    pub fn find_all_by_class(&self, class: &str) -> Vec<&Element> {
+        self.elements().flat_map(|e| e.find_all_by_class(class)).collect()
+    }
+    pub fn walk<V: Visitor + ?Sized>(&self, v: &mut V) {
+        for e in self.elements() {
+            e.walk(v);
+        }
+    }
+    pub fn walk_mut<V: VisitorMut + ?Sized>(&mut self, v: &mut V) {
+        for e in self.elements_mut() {
+            e.walk_mut(v);
+        }
+    }
+    pub fn write_to<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        let mut w = IoFmt::new(w);
+        match self.write_page(&mut w,Vec::new()) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(w.error.take().unwrap_or_else(|| io::Error::other("formatting error"))),
+        }
+    }
+    // for rows and items produced while the page is written
+    pub fn into_stream(self) -> PageStream {
+        PageStream { page: self, parts: Vec::new() }
     }
     
     pub fn drawer(&mut self) -> &mut TableDrawer {
         &mut self.tables
     }
-    pub fn add_tables(&mut self, tb: &TableBuilder) {        
-        let s = tb.styles(&self.tables);
-        if s != "" {
-            if self.css != "" { self.css += "\n"; }
-            self.css += &s;
+    // table styles are merged and deduplicated, minified if asked
+    pub fn with_optimized_styles(mut self, minify: bool) -> HtmlProducer {
+        self.optimize_styles = Some(minify);
+        self
+    }
+    // the rows recorded in the page drawer, as dependencies shared with pushed table views
+    pub fn add_tables(&mut self, tb: &TableBuilder) {
+        let deps = tb.dependencies(&self.tables);
+        self.add_dependencies(deps);
+    }
+}
+
+// A page whose rest is produced while it is written, once, by the consuming render_to/write_to.
+// Parts follow the elements of the page in the order they are pushed.
+pub struct PageStream {
+    page: HtmlProducer,
+    parts: Vec<BodyPart>,
+}
+impl fmt::Debug for PageStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PageStream").field("page",&self.page).field("parts",&self.parts.len()).finish()
+    }
+}
+impl PageStream {
+    pub fn push_block(&mut self, b: Block) {
+        self.parts.push(BodyPart::Element(b.into()));
+    }
+    pub fn push_element(&mut self, e: Element) {
+        self.parts.push(BodyPart::Element(e));
+    }
+    pub fn push_component<C: Component + ?Sized>(&mut self, c: &C) {
+        self.page.add_dependencies(c.dependencies());
+        self.push_element(c.render());
+    }
+    // items are written one per line
+    pub fn push_lazy<I>(&mut self, items: I)
+    where I: IntoIterator,
+          I::IntoIter: Send + 'static,
+          I::Item: fmt::Display,
+    {
+        let items = items.into_iter();
+        self.parts.push(BodyPart::Lazy(Box::new(move |w: &mut dyn fmt::Write| {
+            for item in items {
+                writeln!(w,"{}",item)?;
+            }
+            Ok(())
+        })));
+    }
+    // rows are streamed with TableBuilder::write_row when the page is written, the row and what
+    // its options need are recorded in the page drawer at once, so add_tables has to be called afterwards
+    pub fn push_rows<I,V,S>(&mut self, tb: Arc<TableBuilder>, row: RowRef, class: &str, options: &RowOptions, rows: I) -> Result<(),TableError>
+    where I: IntoIterator<Item = V>,
+          I::IntoIter: Send + 'static,
+          V: AsRef<[S]>,
+          S: AsRef<str>,
+    {
+        self.push_keyed_rows(tb,row,class,options,rows.into_iter().map(|v| (None::<String>,v)))
+    }
+    // rows of a selectable table, each with its checkbox key
+    pub fn push_keyed_rows<I,K,V,S>(&mut self, tb: Arc<TableBuilder>, row: RowRef, class: &str, options: &RowOptions, rows: I) -> Result<(),TableError>
+    where I: IntoIterator<Item = (Option<K>,V)>,
+          I::IntoIter: Send + 'static,
+          K: ToString,
+          V: AsRef<[S]>,
+          S: AsRef<str>,
+    {
+        tb.prepare_row(row,options,&mut self.page.tables)?;
+        let class = class.to_string();
+        let options = options.clone();
+        let rows = rows.into_iter();
+        self.parts.push(BodyPart::Lazy(Box::new(move |w: &mut dyn fmt::Write| {
+            let mut drawer = TableDrawer::new();
+            for (key,values) in rows {
+                let keyed;
+                let opts = match key {
+                    Some(key) => { keyed = options.clone().key(key); &keyed },
+                    None => &options,
+                };
+                tb.write_row(w,row,&class,opts,values.as_ref(),&mut drawer).map_err(|_| fmt::Error)?;
+                w.write_str("\n")?;
+            }
+            Ok(())
+        })));
+        Ok(())
+    }
+    pub fn drawer(&mut self) -> &mut TableDrawer {
+        &mut self.page.tables
+    }
+    pub fn add_tables(&mut self, tb: &TableBuilder) {
+        self.page.add_tables(tb);
+    }
+    pub fn render_to<W: fmt::Write + ?Sized>(self, mut w: &mut W) -> fmt::Result {
+        self.page.write_page(&mut w,self.parts)
+    }
+    pub fn write_to<W: io::Write + ?Sized>(self, w: &mut W) -> io::Result<()> {
+        let mut w = IoFmt::new(w);
+        match self.render_to(&mut w) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(w.error.take().unwrap_or_else(|| io::Error::other("formatting error"))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("logs",300).unwrap();
+        let row = tb.create_row_fixed(table,&[None,Some(60)]).unwrap();
+        let jobs = tb.table_fixed("jobs",300).unwrap();
+        tb.with_selection(jobs,Selection::new("jobs_bulk","/jobs/bulk").action("retry","Retry")).unwrap();
+        let job = tb.create_row_fixed(jobs,&[None,Some(60)]).unwrap();
+        let tb = Arc::new(tb);
+
+        let mut page = HtmlProducer::default().with_title("Logs").with_document(Document::bare());
+        page.push_block(Block::new("head").id("h").text("Logs").sub(Block::new("sub").onclick("go()")));
+        let mut out = Vec::new();
+        page.write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),page.to_string());
+        let mut page = page.into_stream();
+        page.push_rows(tb.clone(),row,"logs_row",&RowOptions::new(),(0..2).map(|i| vec![format!("line {}",i),"ok".to_string()])).unwrap();
+        page.push_lazy((0..2).map(|i| Block::new("foot").text(i)));
+        page.push_keyed_rows(tb.clone(),job,"job",&RowOptions::new().href("/jobs"),(7..8).map(|i| (Some(i),["build","ok"]))).unwrap();
+        page.add_tables(&tb);
+
+        let mut out = Vec::new();
+        page.write_to(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("<html>\n<head>\n<title>\nLogs\n</title>\n<style>\n.logs_r0_c0"));
+        assert!(out.contains("<body>\n<div id='h' class='head'>Logs<div class='sub' onclick='go()'></div>\n</div>\n\
+<div class='logs_row'>\n<div class='logs_r0_c0'>line 0</div>\n<div class='logs_r0_c1'>ok</div>\n</div>\n\
+<div class='logs_row'>\n<div class='logs_r0_c0'>line 1</div>\n<div class='logs_r0_c1'>ok</div>\n</div>\n\
+<div class='foot'>0</div>\n<div class='foot'>1</div>\n<div class='job row_link' data-row-href='/jobs'>"));
+        assert!(out.contains("<input type='checkbox' form='jobs_bulk' name='keys' value='7' data-select='jobs_bulk'>"));
+        assert!(out.contains(".row_link { cursor: pointer; }") && out.contains(".bulk_actions {"));
+        assert!(out.contains("function bulkUpdate(form)"));
+
+        let mut other = TableBuilder::new();
+        for name in ["a","b","c"] { other.table_fixed(name,100).unwrap(); }
+        let d = other.table_fixed("d",100).unwrap();
+        let foreign = other.create_row_fixed(d,&[None]).unwrap();
+        let mut page = HtmlProducer::default().into_stream();
+        assert!(matches!(page.push_rows(tb,foreign,"x",&RowOptions::new(),vec![["a"]]),Err(TableError::UnknownRow(_))));
+    }
+
+    #[test]
+    fn html_macro() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("jobs",300).unwrap();
+        let row = tb.create_row_fixed(table,&[None,Some(60)]).unwrap();
+        let mut drawer = TableDrawer::new();
+        let mut page = HtmlProducer::default();
+        let tabs = Tabs::new(&mut page,vec![Tab { name: "All".to_string(), count: 2, active: true, href: "/".to_string() }]);
+        let jobs = [("build","ok"),("<deploy>","failed")];
+        let page_no = 2;
+        let e = html! {
+            div(class = "jobs", data-page = page_no, hidden) {
+                h1 { "Jobs & " (jobs.len()) }
+                for (name,status) in jobs.iter().filter(|(_,s)| *s != "skipped") {
+                    div(class = format!("job_{}",status)) { (name) }
+                }
+                if jobs.is_empty() { span(class = "empty") { "none" } } else if jobs.len() > 5 { "many" } else { hr; }
+                if let Some((n,_)) = jobs.first() { b { (n) } }
+                @node(tabs.blocks())
+                @raw(tb.row(row,"job_row",vec!["a".to_string(),"b".to_string()],&mut drawer).unwrap())
+                input(type = "checkbox", checked);
+            }
+        };
+        let s = e.to_string();
+        assert!(s.starts_with("<div class='jobs' data-page='2' hidden><h1>Jobs &amp; 2</h1>\
+<div class='job_ok'>build</div><div class='job_failed'>&lt;deploy&gt;</div><hr><b>build</b><div class='tabs'>"));
+        assert!(s.ends_with("<div class='job_row'>\n<div class='jobs_r0_c0'>a</div>\n<div class='jobs_r0_c1'>b</div>\n</div>\
+<input type='checkbox' checked></div>"));
+        assert_eq!(html!(br).to_string(),"<br>");
+    }
+
+    #[test]
+    fn page_query() {
+        let mut page = HtmlProducer::default();
+        let mut tabs = Tabs::new(&mut page,vec![
+            Tab { name: "All".to_string(), count: 3, active: false, href: "/".to_string() },
+            Tab { name: "Errors".to_string(), count: 1, active: false, href: "/e".to_string() },
+        ]);
+        tabs.set_active("Errors");
+        page.push_block(tabs.blocks().id("tabs"));
+        page.push_block(Block::new("job").id("j1").sub(Block::new("status").class("error")));
+        page.insert_element(0,Element::new("div").class("banner").text("maintenance"));
+
+        assert_eq!(page.find_all_by_class("tab_button").len(),1);
+        assert_eq!(page.find_all_by_class("tab_button_active").len(),1);
+        assert_eq!(page.find_by_id("tabs").map(|e| e.children().len()),Some(2));
+        page.walk_mut(&mut |e: &mut Element| if e.has_class("error") { e.add_class("error_row") });
+        page.find_by_id_mut("j1").unwrap().set_attr("data-key","1");
+        let s = page.to_string();
+        assert!(s.contains("<body>\n<div class='banner'>maintenance</div>\n<div id='tabs' class='tabs'>"));
+        assert!(s.contains("<div class='tab_button' onclick='tabClicked(&quot;/&quot;);'>All"));
+        assert!(s.contains("<div id='j1' class='job' data-key='1'><div class='status error error_row'></div>\n</div>"));
+    }
+
+    #[test]
+    fn components() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("jobs",300).unwrap();
+        let r0 = tb.create_row_fixed(table,&[None,Some(60)]).unwrap();
+        let r1 = tb.create_row_fixed(table,&[Some(100),None]).unwrap();
+
+        let mut page = HtmlProducer::default();
+        let tabs = Tabs::new(&mut page,vec![Tab { name: "All".to_string(), count: 2, active: false, href: "/".to_string() }]);
+        page.push_component(&tabs);
+        page.push_component(&tabs);
+        let mut first = tb.view();
+        first.row(r0,"job",&RowOptions::new().href("/j/1"),&["a","b"]).unwrap();
+        let mut second = tb.view();
+        second.row(r0,"job",&RowOptions::new().href("/j/2"),&["c","d"]).unwrap();
+        second.row(r1,"job",&RowOptions::new(),&["e","f"]).unwrap();
+        page.push_component(&first);
+        page.push_component(&second);
+
+        let s = page.to_string();
+        assert_eq!(s.matches("closest('[data-tab-href]')").count(),1);
+        assert!(!s.contains("tabClicked"));
+        assert_eq!(s.matches(".tab_row {").count(),1);
+        assert_eq!(s.matches(".jobs_r0_c0 {").count(),1);
+        assert_eq!(s.matches(".jobs_r1_c0 {").count(),1);
+        assert_eq!(s.matches(".row_link {").count(),1);
+        assert_eq!(s.matches("<div class='tabs'>").count(),2);
+        assert!(s.contains("<div><div class='job row_link' data-row-href='/j/1'>"));
+
+        // rows of the page drawer and of pushed views share their styles
+        let mut page = HtmlProducer::default().with_optimized_styles(true).with_debug_layout();
+        tb.row(r0,"job",vec!["a".to_string(),"b".to_string()],page.drawer()).unwrap();
+        page.push_component(&second);
+        page.add_tables(&tb);
+        let s = page.to_string();
+        assert_eq!(s.matches(".jobs_r0_c0{").count(),1);
+        assert_eq!(s.matches(".jobs_r1_c0{").count(),1);
+        assert!(!s.contains(".jobs_r0_c0 {"));
+        assert_eq!(s.matches(".jobs_r0_c0, .jobs_r0_c1 { outline").count(),1);
+    }
+
+    #[test]
+    fn owned_styles() {
+        let accent = String::from("#3366ff");
+        let width = 40 + 2;
+        let status = "failed".to_string();
+        let base = Style::new(format!("badge_{}",status)).opt("color",accent.clone()).opt("width",format!("{}px",width));
+        let s = base.duplicate("badge").opt("color","red");
+        assert_eq!(base.to_string(),".badge_failed {    color: #3366ff;\n    width: 42px;\n}");
+        assert_eq!(s.to_string(),".badge {    color: red;\n    width: 42px;\n}");
+        assert_eq!(Block::new(format!("job_{}",status)).text(classed(status.clone(),1)).to_string(),
+                   "<div class='job_failed'><span class='failed'>1</span></div>");
+    }
+
+    #[test]
+    fn themed_page() {
+        let themes = Themes::new();
+        // components carry their themed look, without the default styles
+        let mut page = HtmlProducer::default().with_themes(&themes);
+        page.push_component(&themes.toggle());
+        page.push_component(&Badge::new("failed",BadgeKind::Danger));
+        page.push_component(&Badge::new("ok",BadgeKind::Success));
+        let s = page.to_string();
+        assert!(s.contains("<style>\n:root {\n    color-scheme: light;"));
+        assert_eq!(s.matches(".badge {").count(),1);
+        assert!(s.contains(".badge_danger { background: var(--ui-danger, #cf222e); }"));
+        assert!(s.contains(".theme_toggle { background: var(--ui-surface, inherit);"));
+        assert!(s.find(".badge_danger {").unwrap() > s.find(":root {").unwrap());
+        assert_eq!(s.matches("localStorage.getItem('ui-theme')").count(),1);
+    }
+
+    #[test]
+    fn default_styles() {
+        let tabs = |page: &mut HtmlProducer| Tabs::new(page,vec![Tab { name: "All".to_string(), count: 0, active: false, href: "/".to_string() }]);
+        let mut page = HtmlProducer::default();
+        let t = tabs(&mut page);
+        page.push_component(&t);
+        assert!(!page.to_string().contains(".tab_button {"));
+
+        let defaults = DefaultStyles::new().with(Style::new("tab_button").opt("background","#123"));
+        let mut page = HtmlProducer::default().with_default_styles(&defaults);
+        let t = tabs(&mut page);
+        page.push_component(&t);
+        page.add_dependencies(vec![Dependency::new("legacy").css(".tab_button { background: #eee; }")]);
+        let s = page.to_string();
+        assert_eq!(s.matches(".tab_button {").count(),2);
+        assert!(s.find(".tab_button { background: #123; }").unwrap() > s.find(".tab_button { background: #eee; }").unwrap());
+        assert!(s.find(".tab_button_active { padding").unwrap() > s.find(".tab_row { display: flex;").unwrap());
+    }
+
+    #[test]
+    fn scoped_page() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_soft("jobs",600).unwrap();
+        let row = tb.create_row_soft(table,vec![SoftColumn { percentage: None, subcolumns: vec![Some(100),None,Some(40)] }]).unwrap();
+        let mut page = HtmlProducer::default().with_default_styles(&DefaultStyles::new()).with_scope(Scope::new("ui").global("job"));
+        let tabs = Tabs::new(&mut page,vec![Tab { name: "All".to_string(), count: 0, active: true, href: "/".to_string() }]);
+        page.push_component(&tabs);
+        let mut view = tb.view();
+        view.row(row,"job",&RowOptions::new().detail(Block::new("more")),&["a","b","c"]).unwrap();
+        page.push_component(&view);
+        let s = page.to_string();
+        assert!(s.contains(".ui-tab_button { padding"));
+        assert!(s.contains(".ui-tab_button:hover { color"));
+        assert!(s.contains(".ui-tab_row { display: flex;"));
+        assert!(s.contains(".ui-jobs_r0_l {"));
+        assert!(s.contains("<div class='ui-tabs'><div class='ui-tab_row'>"));
+        assert!(s.contains("<div class='job ui-row_link' data-row-detail='1'>"));
+        assert!(s.contains("<div class='ui-row'>"));
+        assert!(s.contains("<div class='row_detail' hidden>\n<div class='ui-more'>"));
+        assert!(!s.contains("class='tabs'"));
+    }
+
+    #[test]
+    fn document() {
+        let page = HtmlProducer::default().with_title("Jobs").with_document(Document::new().lang("uk").favicon(Favicon::url("/f.ico")));
+        assert!(page.to_string().starts_with("<!DOCTYPE html>\n<html lang='uk'>\n<head>\n<meta charset='utf-8'>\n\
+<meta name='viewport' content='width=device-width, initial-scale=1'>\n<link rel='icon' href='/f.ico'>\n<title>\nJobs\n</title>\n"));
+        assert!(HtmlProducer::default().to_string().starts_with("<!DOCTYPE html>\n<html lang='en'>\n<head>\n<meta charset='utf-8'>\n"));
+    }
+
+    #[test]
+    fn linked_assets() {
+        let mut reg = AssetRegistry::new("/assets");
+        let css = reg.register("app.css",b".job { color: red; }".to_vec());
+        reg.register("app.js",b"console.log(1);".to_vec());
+        let page = HtmlProducer::default().with_document(Document::bare()).with_title("Jobs")
+            .with_style_link(&css).with_style_link(&css)
+            .with_script_src(reg.script("app.js").unwrap().defer())
+            .with_script_src(Script::new("/lib.mjs").module())
+            .with_scripts("go();");
+        let s = page.to_string();
+        assert!(s.starts_with(&format!("<html>\n<head>\n<title>\nJobs\n</title>\n<link rel='stylesheet' href='{}'>\n<style>\n",css)));
+        assert!(s.contains(&format!("\n</style>\n<script src='{}' defer></script>\n<script src='/lib.mjs' type='module'></script>\n<script>\ngo();\n",reg.url("app.js").unwrap())));
+        assert_eq!(s.matches("rel='stylesheet'").count(),1);
+    }
+
+    #[test]
+    fn csp_page() {
+        let csp = Csp::new().with_nonce("n0nce");
+        let mut page = HtmlProducer::default().with_document(Document::bare()).with_csp(csp)
+            .with_script_src(Script::new("/app.js").defer()).with_scope(Scope::new("ui"));
+        let tabs = Tabs::new(&mut page,vec![
+            Tab { name: "All".to_string(), count: 0, active: true, href: "/".to_string() },
+            Tab { name: "Errors".to_string(), count: 1, active: false, href: "/e".to_string() },
+        ]);
+        page.push_component(&tabs);
+        page.push_block(tabs.blocks());
+        page.push_block(Block::new("job").onclick("go('<1>')").sub(Block::new("status").on("mouseover","hl(this)")));
+        let s = page.to_string();
+        assert!(s.contains("<style nonce='n0nce'>\n"));
+        assert!(s.contains("<script src='/app.js' defer nonce='n0nce'></script>\n<script nonce='n0nce'>\n"));
+        assert!(s.contains("<div class='ui-tab_button' data-tab-href='/e'>"));
+        assert!(s.contains("<div class='ui-tab_button' data-ui-onclick='0'>"));
+        assert!(s.contains("<div class='ui-job' data-ui-onclick='1'><div class='ui-status' data-ui-onmouseover='2'></div>"));
+        assert!(s.contains("function(event) { tabClicked(\"/e\");\n"));
+        assert!(s.contains("function(event) { go('<1>')\n"));
+        assert!(s.ends_with("['click','mouseover'].forEach(function(t) { if (!seen[t]) { seen[t] = true; document.addEventListener(t, window.uiRun, true); } });\n})();\n</script>\n\n</body>\n</html>\n"));
+        assert!(!s.contains(" on"));
+        assert_eq!(page.csp_header().unwrap().split("; ").nth(1),Some("script-src 'nonce-n0nce' 'strict-dynamic'"));
+        // style='..' of Block::style
+        assert!(page.csp_header().unwrap().contains("; style-src-attr 'unsafe-inline';"));
+    }
+
+    #[test]
+    fn block_attributes() {
+        let status = "failed".to_string();
+        let b = Block::new("job").class(format!("job_{}",status)).data("id",17).style("min-width","40px")
+            .on("mouseover","show('<17>')").onclick("go(\"17\")").text("x");
+        assert_eq!(b.to_string(),"<div class='job job_failed' data-id='17' onmouseover='show(&#39;&lt;17&gt;&#39;)' \
+onclick='go(&quot;17&quot;)' style='min-width: 40px;'>x</div>");
+    }
+}
@@ -0,0 +1,109 @@
+// Declarative element trees, expands to Element builders:
+//
+//     html! {
+//         div(class = "jobs", data-page = page) {
+//             h1 { "Jobs " (count) }
+//             for job in &jobs {
+//                 div(class = "job", id = job.id) { (job.name) }
+//             }
+//             if jobs.is_empty() { span(class = "empty") { "nothing here" } } else { hr; }
+//             @node(tabs.blocks())
+//             @raw(tb.row(row,"job_row",values,&mut drawer)?)
+//         }
+//     }
+//
+// Literals and (expr) are text and get escaped, @raw(expr) is written as is,
+// @node(expr) inserts anything convertible into Node (Element, Block).
+// Attributes without a value are boolean ones.
+#[macro_export]
+macro_rules! html {
+    (@attrs $el:ident;) => {};
+    (@attrs $el:ident; class = $v:expr $(, $($rest:tt)*)?) => {
+        $el = $el.class($v);
+        $crate::html!(@attrs $el; $($($rest)*)?);
+    };
+    (@attrs $el:ident; id = $v:expr $(, $($rest:tt)*)?) => {
+        $el = $el.id($v);
+        $crate::html!(@attrs $el; $($($rest)*)?);
+    };
+    (@attrs $el:ident; $n:ident $(- $ns:ident)* = $v:expr $(, $($rest:tt)*)?) => {
+        $el = $el.attr(concat!(stringify!($n) $(, "-", stringify!($ns))*),$v);
+        $crate::html!(@attrs $el; $($($rest)*)?);
+    };
+    (@attrs $el:ident; $n:ident $(- $ns:ident)* $(, $($rest:tt)*)?) => {
+        $el = $el.flag(concat!(stringify!($n) $(, "-", stringify!($ns))*));
+        $crate::html!(@attrs $el; $($($rest)*)?);
+    };
+
+    // condition and iterator tokens are collected up to the body block,
+    // else-if chains are collected in front of the last condition
+    (@if $el:ident; [$($chain:tt)*] [$($c:tt)*] { $($body:tt)* } else if $($rest:tt)*) => {
+        $crate::html!(@if $el; [$($chain)* if $($c)* { $crate::html!(@children $el; $($body)*); } else] [] $($rest)*);
+    };
+    (@if $el:ident; [$($chain:tt)*] [$($c:tt)*] { $($body:tt)* } else { $($eb:tt)* } $($rest:tt)*) => {
+        $($chain)* if $($c)* { $crate::html!(@children $el; $($body)*); } else { $crate::html!(@children $el; $($eb)*); }
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@if $el:ident; [$($chain:tt)*] [$($c:tt)*] { $($body:tt)* } $($rest:tt)*) => {
+        $($chain)* if $($c)* { $crate::html!(@children $el; $($body)*); }
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@if $el:ident; [$($chain:tt)*] [$($c:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::html!(@if $el; [$($chain)*] [$($c)* $next] $($rest)*);
+    };
+    (@for $el:ident; [$p:pat] [$($it:tt)*] { $($body:tt)* } $($rest:tt)*) => {
+        for $p in $($it)* { $crate::html!(@children $el; $($body)*); }
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@for $el:ident; [$p:pat] [$($it:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::html!(@for $el; [$p] [$($it)* $next] $($rest)*);
+    };
+
+    (@children $el:ident;) => {};
+    (@children $el:ident; $t:literal $($rest:tt)*) => {
+        $el.push($crate::Node::Text($t.to_string()));
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@children $el:ident; ($e:expr) $($rest:tt)*) => {
+        $el.push($crate::Node::Text(($e).to_string()));
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@children $el:ident; @raw($e:expr) $($rest:tt)*) => {
+        $el.push($crate::Node::Raw(($e).to_string()));
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@children $el:ident; @node($e:expr) $($rest:tt)*) => {
+        $el.push($e);
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@children $el:ident; if $($rest:tt)*) => {
+        $crate::html!(@if $el; [] [] $($rest)*);
+    };
+    (@children $el:ident; for $p:pat in $($rest:tt)*) => {
+        $crate::html!(@for $el; [$p] [] $($rest)*);
+    };
+    (@children $el:ident; $tag:ident ($($a:tt)*) { $($c:tt)* } $($rest:tt)*) => {
+        $el.push($crate::html!($tag ($($a)*) { $($c)* }));
+        $crate::html!(@children $el; $($rest)*);
+    };
+    (@children $el:ident; $tag:ident { $($c:tt)* } $($rest:tt)*) => {
+        $crate::html!(@children $el; $tag () { $($c)* } $($rest)*);
+    };
+    (@children $el:ident; $tag:ident ($($a:tt)*); $($rest:tt)*) => {
+        $crate::html!(@children $el; $tag ($($a)*) {} $($rest)*);
+    };
+    (@children $el:ident; $tag:ident; $($rest:tt)*) => {
+        $crate::html!(@children $el; $tag () {} $($rest)*);
+    };
+
+    ($tag:ident ($($a:tt)*) { $($c:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut el = $crate::Element::new(stringify!($tag));
+        $crate::html!(@attrs el; $($a)*);
+        $crate::html!(@children el; $($c)*);
+        el
+    }};
+    ($tag:ident { $($c:tt)* }) => { $crate::html!($tag () { $($c)* }) };
+    ($tag:ident ($($a:tt)*)) => { $crate::html!($tag ($($a)*) {}) };
+    ($tag:ident) => { $crate::html!($tag () {}) };
+}
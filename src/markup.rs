@@ -0,0 +1,159 @@
+use std::fmt;
+
+// Rewrites attributes of start tags: the new name and value, None keeps the attribute as it is.
+pub(crate) trait AttrFilter {
+    fn attr(&mut self, name: &str, value: &str) -> Option<(String,String)>;
+}
+
+enum State {
+    Text,
+    // after '<', the tag name so far
+    Open(String),
+    // in a start tag, between attributes
+    Tag,
+    // the name of an attribute, then the spaces after it
+    Name,
+    AfterName,
+    // after name=
+    Eq,
+    // the quote of the value
+    Value(Option<char>),
+    // the count of '-' before the current char
+    Comment(usize),
+    // script and style content up to "</script" or "</style", the matched length
+    RawText(&'static str,usize),
+}
+
+// Passes markup through, chunks may split it anywhere. Attributes with a value are given
+// to the filter once complete; text, comments and the content of script and style elements
+// are left alone.
+pub(crate) struct AttrWriter<'a, W: fmt::Write + ?Sized, F: AttrFilter> {
+    inner: &'a mut W,
+    pub(crate) filter: F,
+    state: State,
+    tag: String,
+    // the attribute being read: name, what is between name and value, value
+    name: String,
+    mid: String,
+    value: String,
+}
+impl<'a, W: fmt::Write + ?Sized, F: AttrFilter> AttrWriter<'a,W,F> {
+    pub(crate) fn new(inner: &'a mut W, filter: F) -> AttrWriter<'a,W,F> {
+        AttrWriter { inner, filter, state: State::Text, tag: String::new(), name: String::new(), mid: String::new(), value: String::new() }
+    }
+    // an unfinished attribute is written as it is
+    pub(crate) fn finish(mut self) -> Result<F,fmt::Error> {
+        let mut out = std::mem::take(&mut self.name);
+        out += &self.mid;
+        out += &self.value;
+        self.inner.write_str(&out)?;
+        Ok(self.filter)
+    }
+    // the end of a start or end tag
+    fn close(&self) -> State {
+        match self.tag.as_str() {
+            "script" => State::RawText("</script",0),
+            "style" => State::RawText("</style",0),
+            _ => State::Text,
+        }
+    }
+    // an attribute without a value
+    fn flush(&mut self, out: &mut String) {
+        *out += &self.name;
+        *out += &self.mid;
+        self.name.clear();
+        self.mid.clear();
+    }
+    fn emit(&mut self, out: &mut String, quote: Option<char>) {
+        let (name,value) = (std::mem::take(&mut self.name),std::mem::take(&mut self.value));
+        let (name,value) = self.filter.attr(&name,&value).unwrap_or((name,value));
+        *out += &name;
+        *out += &self.mid;
+        *out += &value;
+        if let Some(q) = quote { out.push(q); }
+        self.mid.clear();
+    }
+    fn tag_char(&mut self, c: char, out: &mut String) -> State {
+        match c {
+            '>' => { out.push(c); self.close() },
+            c if c.is_whitespace() || (c == '/') => { out.push(c); State::Tag },
+            c => { self.name.push(c); State::Name },
+        }
+    }
+    fn step(&mut self, c: char, out: &mut String) -> State {
+        match std::mem::replace(&mut self.state,State::Text) {
+            State::Text => {
+                out.push(c);
+                match c {
+                    '<' => State::Open(String::new()),
+                    _ => State::Text,
+                }
+            },
+            State::Open(name) if (name == "!-") && (c == '-') => { out.push(c); State::Comment(0) },
+            State::Open(name) if name.is_empty() && !(c.is_ascii_alphabetic() || (c == '/') || (c == '!')) => { out.push(c); State::Text },
+            State::Open(mut name) if !(c.is_whitespace() || (c == '>') || (c == '/' && !name.is_empty())) => {
+                out.push(c);
+                name.push(c);
+                State::Open(name)
+            },
+            State::Open(name) => {
+                self.tag = name.to_ascii_lowercase();
+                out.push(c);
+                match c {
+                    '>' => self.close(),
+                    _ => State::Tag,
+                }
+            },
+            State::Tag => self.tag_char(c,out),
+            State::Name => match c {
+                '=' => { self.mid.push(c); State::Eq },
+                c if c.is_whitespace() => { self.mid.push(c); State::AfterName },
+                '>' | '/' => { self.flush(out); self.tag_char(c,out) },
+                c => { self.name.push(c); State::Name },
+            },
+            State::AfterName => match c {
+                '=' => { self.mid.push(c); State::Eq },
+                c if c.is_whitespace() => { self.mid.push(c); State::AfterName },
+                c => { self.flush(out); self.tag_char(c,out) },
+            },
+            State::Eq => match c {
+                '\'' | '"' => { self.mid.push(c); State::Value(Some(c)) },
+                '>' => { self.flush(out); self.tag_char(c,out) },
+                c if c.is_whitespace() => { self.mid.push(c); State::Eq },
+                c => { self.value.push(c); State::Value(None) },
+            },
+            State::Value(Some(q)) if c == q => { self.emit(out,Some(q)); State::Tag },
+            State::Value(None) if c.is_whitespace() || (c == '>') => { self.emit(out,None); self.tag_char(c,out) },
+            State::Value(q) => { self.value.push(c); State::Value(q) },
+            State::Comment(dashes) => {
+                out.push(c);
+                match c {
+                    '>' if dashes >= 2 => State::Text,
+                    '-' => State::Comment(dashes + 1),
+                    _ => State::Comment(0),
+                }
+            },
+            State::RawText(end,matched) => {
+                out.push(c);
+                let matched = match end[matched..].chars().next() {
+                    Some(e) if e.eq_ignore_ascii_case(&c) => matched + 1,
+                    _ if c == '<' => 1,
+                    _ => 0,
+                };
+                match matched == end.len() {
+                    true => State::Open(end[1..].to_string()),
+                    false => State::RawText(end,matched),
+                }
+            },
+        }
+    }
+}
+impl<W: fmt::Write + ?Sized, F: AttrFilter> fmt::Write for AttrWriter<'_,W,F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut out = String::with_capacity(s.len());
+        for c in s.chars() {
+            self.state = self.step(c,&mut out);
+        }
+        self.inner.write_str(&out)
+    }
+}
@@ -74,7 +74,7 @@ impl ResourceManager {
                 let (mut rc,inner) = recource_with_updates(fl)?;
                 match &self.sender {
                     None => { rc.updates = None; },
-                    Some(sender) => if let Err(_) = sender.send(inner) {
+                    Some(sender) => if sender.send(inner).is_err() {
                         rc.updates = None;
                     },
                 }
@@ -0,0 +1,150 @@
+use std::{
+    fmt,
+    borrow::Cow,
+};
+
+use super::markup::{AttrWriter,AttrFilter};
+
+// Classes whose names the client scripts depend on, they are never prefixed.
+const SCRIPT_CLASSES: [&str; 6] = ["row_detail","cell_edit","cell_saving","cell_saved","cell_error","bulk_actions"];
+
+// Prefix for every class of a page: "tabs" becomes "{prefix}-tabs" in the markup and in the css,
+// so the page can live next to site css. Classes marked global keep their names.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Scope {
+    prefix: String,
+    global: Vec<String>,
+}
+impl Scope {
+    // characters that can't be in a class name are dropped
+    pub fn new<T: ToString>(prefix: T) -> Scope {
+        Scope {
+            prefix: prefix.to_string().chars().filter(|c| c.is_alphanumeric() || (*c == '_') || (*c == '-')).collect(),
+            global: SCRIPT_CLASSES.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+    pub fn global<T: ToString>(mut self, class: T) -> Scope {
+        self.global.push(class.to_string());
+        self
+    }
+    pub fn is_global(&self, class: &str) -> bool {
+        self.prefix.is_empty() || self.global.iter().any(|g| g == class)
+    }
+    pub fn class<'a>(&self, class: &'a str) -> Cow<'a,str> {
+        match self.is_global(class) {
+            true => Cow::Borrowed(class),
+            false => Cow::Owned(format!("{}-{}",self.prefix,class)),
+        }
+    }
+
+    // class selectors of style rules, also inside @media and @supports blocks
+    pub fn css(&self, css: &str) -> String {
+        #[derive(Clone,Copy,PartialEq)]
+        enum Ctx { Rules, Decls }
+        let chars = css.chars().collect::<Vec<_>>();
+        let mut res = String::with_capacity(css.len() + css.len() / 4);
+        let mut stack = vec![Ctx::Rules];
+        let mut prelude = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let ctx = *stack.last().unwrap_or(&Ctx::Rules);
+            match c {
+                '"' | '\'' => {
+                    let end = chars[i+1..].iter().position(|q| *q == c).map(|p| i + 1 + p).unwrap_or(chars.len() - 1);
+                    let s = chars[i ..= end].iter().collect::<String>();
+                    if ctx == Ctx::Rules { prelude += &s; }
+                    res += &s;
+                    i = end + 1;
+                    continue;
+                },
+                '{' => {
+                    let p = prelude.trim();
+                    stack.push(match p.starts_with("@media") || p.starts_with("@supports") || p.starts_with("@container") || p.starts_with("@layer") {
+                        true if ctx == Ctx::Rules => Ctx::Rules,
+                        _ => Ctx::Decls,
+                    });
+                    prelude.clear();
+                },
+                '}' => { stack.pop(); prelude.clear(); },
+                ';' if ctx == Ctx::Rules => prelude.clear(),
+                '.' if (ctx == Ctx::Rules) && !prelude.trim_start().starts_with('@')
+                    && chars.get(i+1).map(|n| n.is_alphabetic() || (*n == '_') || (*n == '-')).unwrap_or(false) => {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < chars.len() && (chars[end].is_alphanumeric() || (chars[end] == '_') || (chars[end] == '-')) { end += 1; }
+                    let class = chars[start..end].iter().collect::<String>();
+                    let cls = format!(".{}",self.class(&class));
+                    prelude += &cls;
+                    res += &cls;
+                    i = end;
+                    continue;
+                },
+                _ => if ctx == Ctx::Rules { prelude.push(c); },
+            }
+            res.push(c);
+            i += 1;
+        }
+        res
+    }
+}
+
+// class='..' gets the scope
+struct Classes<'a>(&'a Scope);
+impl AttrFilter for Classes<'_> {
+    fn attr(&mut self, name: &str, value: &str) -> Option<(String,String)> {
+        if !name.eq_ignore_ascii_case("class") { return None; }
+        let mut res = String::with_capacity(value.len() + 8);
+        for (i,c) in value.split_whitespace().enumerate() {
+            if i > 0 { res.push(' '); }
+            res += &self.0.class(c);
+        }
+        Some((name.to_string(),res))
+    }
+}
+
+// Rewrites the class attributes of tags in the markup passing through, chunks may split them anywhere.
+pub(crate) struct ScopedWriter<'a, W: fmt::Write + ?Sized>(AttrWriter<'a,W,Classes<'a>>);
+impl<'a, W: fmt::Write + ?Sized> ScopedWriter<'a,W> {
+    pub(crate) fn new(inner: &'a mut W, scope: &'a Scope) -> ScopedWriter<'a,W> {
+        ScopedWriter(AttrWriter::new(inner,Classes(scope)))
+    }
+    pub(crate) fn finish(self) -> fmt::Result {
+        self.0.finish().map(|_| ())
+    }
+}
+impl<W: fmt::Write + ?Sized> fmt::Write for ScopedWriter<'_,W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fmt::Write;
+
+    #[test]
+    fn scope() {
+        let s = Scope::new("ui").global("job");
+        assert_eq!(s.css(".tabs, div.tab_row > .job { margin: .5em; background: url('a.b.png'); }
+@media (max-width: 40.5em) { .tabs:hover { content: \".x\"; } }
+@keyframes spin { from { opacity: 0.5; } }
+a[href$='.pdf'] .row_detail { color: red; }
+"),".ui-tabs, div.ui-tab_row > .job { margin: .5em; background: url('a.b.png'); }
+@media (max-width: 40.5em) { .ui-tabs:hover { content: \".x\"; } }
+@keyframes spin { from { opacity: 0.5; } }
+a[href$='.pdf'] .row_detail { color: red; }
+");
+        let mut out = String::new();
+        let mut w = ScopedWriter::new(&mut out,&s);
+        for chunk in ["<div cla","ss='tabs x' data-class='y'><span class='job ce","ll_edit'>subclass='a' class='b'</span></div>",
+                      "<script>if (a<b) { x.innerHTML = \"<i class='c'>\"; }</SCRIPT><!-- <b class='d'> --><i\ntitle=\"class='e'\" ",
+                      "CLASS = f disabled>a < b</i><br/><p cl"] {
+            w.write_str(chunk).unwrap();
+        }
+        w.finish().unwrap();
+        assert_eq!(out,"<div class='ui-tabs ui-x' data-class='y'><span class='job cell_edit'>subclass='a' class='b'</span></div>\
+<script>if (a<b) { x.innerHTML = \"<i class='c'>\"; }</SCRIPT><!-- <b class='d'> --><i\ntitle=\"class='e'\" CLASS = ui-f disabled>a < b</i><br/><p cl");
+    }
+}
@@ -0,0 +1,858 @@
+use std::fmt;
+
+use super::{
+    Style,
+    css::{parse_items,CssItem},
+};
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Unit {
+    Px,
+    Em,
+    Rem,
+    Percent,
+    Fr,
+    Vw,
+    Vh,
+    Deg,
+    Ms,
+    S,
+}
+impl Unit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Px => "px",
+            Unit::Em => "em",
+            Unit::Rem => "rem",
+            Unit::Percent => "%",
+            Unit::Fr => "fr",
+            Unit::Vw => "vw",
+            Unit::Vh => "vh",
+            Unit::Deg => "deg",
+            Unit::Ms => "ms",
+            Unit::S => "s",
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Length {
+    pub value: f64,
+    pub unit: Unit,
+}
+impl Length {
+    pub fn new<T: Into<f64>>(value: T, unit: Unit) -> Length {
+        Length { value: value.into(), unit }
+    }
+    pub fn px<T: Into<f64>>(v: T) -> Length { Length::new(v,Unit::Px) }
+    pub fn em<T: Into<f64>>(v: T) -> Length { Length::new(v,Unit::Em) }
+    pub fn rem<T: Into<f64>>(v: T) -> Length { Length::new(v,Unit::Rem) }
+    pub fn percent<T: Into<f64>>(v: T) -> Length { Length::new(v,Unit::Percent) }
+    pub fn fr<T: Into<f64>>(v: T) -> Length { Length::new(v,Unit::Fr) }
+}
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}{}",self.value,self.unit.as_str())
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum Value {
+    Length(Length),
+    Number(f64),
+    // written as is: colors, keywords, functions
+    Keyword(String),
+    // space separated
+    List(Vec<Value>),
+}
+impl From<Length> for Value {
+    fn from(l: Length) -> Value {
+        Value::Length(l)
+    }
+}
+impl From<f64> for Value {
+    fn from(n: f64) -> Value {
+        Value::Number(n)
+    }
+}
+impl From<i32> for Value {
+    fn from(n: i32) -> Value {
+        Value::Number(n.into())
+    }
+}
+impl From<&str> for Value {
+    fn from(k: &str) -> Value {
+        Value::Keyword(k.to_string())
+    }
+}
+impl From<String> for Value {
+    fn from(k: String) -> Value {
+        Value::Keyword(k)
+    }
+}
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Value {
+        Value::List(v.into_iter().map(Into::into).collect())
+    }
+}
+impl Value {
+    // single numbers and lengths are typed, everything else is kept as a keyword
+    pub fn parse(v: &str) -> Value {
+        let v = v.trim();
+        let num = v.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+')).unwrap_or(v.len());
+        let unit = match &v[num..] {
+            "" => None,
+            "px" => Some(Unit::Px),
+            "em" => Some(Unit::Em),
+            "rem" => Some(Unit::Rem),
+            "%" => Some(Unit::Percent),
+            "fr" => Some(Unit::Fr),
+            "vw" => Some(Unit::Vw),
+            "vh" => Some(Unit::Vh),
+            "deg" => Some(Unit::Deg),
+            "ms" => Some(Unit::Ms),
+            "s" => Some(Unit::S),
+            _ => return Value::Keyword(v.to_string()),
+        };
+        match (v[..num].parse::<f64>(),unit) {
+            (Ok(n),Some(unit)) => Value::Length(Length::new(n,unit)),
+            (Ok(n),None) => Value::Number(n),
+            (Err(_),_) => Value::Keyword(v.to_string()),
+        }
+    }
+}
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Length(l) => l.fmt(f),
+            Value::Number(n) => n.fmt(f),
+            Value::Keyword(k) => f.write_str(k),
+            Value::List(v) => {
+                for (i,v) in v.iter().enumerate() {
+                    if i > 0 { f.write_str(" ")?; }
+                    v.fmt(f)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Nth {
+    Odd,
+    Even,
+    Index(i32),
+    // an+b
+    Step(i32,i32),
+}
+impl fmt::Display for Nth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Nth::Odd => f.write_str("odd"),
+            Nth::Even => f.write_str("even"),
+            Nth::Index(i) => write!(f,"{}",i),
+            Nth::Step(a,b) => match b {
+                0 => write!(f,"{}n",a),
+                _ => write!(f,"{}n{:+}",a,b),
+            },
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Pseudo {
+    Hover,
+    Focus,
+    Active,
+    Visited,
+    Checked,
+    Disabled,
+    FirstChild,
+    LastChild,
+    NthChild(Nth),
+    NthOfType(Nth),
+    Not(Selector),
+    Before,
+    After,
+    // anything else, with its colons
+    Other(String),
+}
+impl fmt::Display for Pseudo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pseudo::Hover => f.write_str(":hover"),
+            Pseudo::Focus => f.write_str(":focus"),
+            Pseudo::Active => f.write_str(":active"),
+            Pseudo::Visited => f.write_str(":visited"),
+            Pseudo::Checked => f.write_str(":checked"),
+            Pseudo::Disabled => f.write_str(":disabled"),
+            Pseudo::FirstChild => f.write_str(":first-child"),
+            Pseudo::LastChild => f.write_str(":last-child"),
+            Pseudo::NthChild(n) => write!(f,":nth-child({})",n),
+            Pseudo::NthOfType(n) => write!(f,":nth-of-type({})",n),
+            Pseudo::Not(s) => write!(f,":not({})",s),
+            Pseudo::Before => f.write_str("::before"),
+            Pseudo::After => f.write_str("::after"),
+            Pseudo::Other(o) => f.write_str(o),
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AttrOp {
+    Equals,
+    Word,
+    Prefix,
+    Suffix,
+    Contains,
+}
+impl AttrOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AttrOp::Equals => "=",
+            AttrOp::Word => "~=",
+            AttrOp::Prefix => "^=",
+            AttrOp::Suffix => "$=",
+            AttrOp::Contains => "*=",
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+    Adjacent,
+    Sibling,
+}
+
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attrs: Vec<(String,Option<(AttrOp,String)>)>,
+    pseudos: Vec<Pseudo>,
+}
+impl fmt::Display for Compound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let empty = self.tag.is_none() && self.id.is_none() && self.classes.is_empty() && self.attrs.is_empty();
+        match &self.tag {
+            Some(t) => f.write_str(t)?,
+            None => if empty && self.pseudos.is_empty() { f.write_str("*")? },
+        }
+        if let Some(id) = &self.id { write!(f,"#{}",id)?; }
+        for c in &self.classes { write!(f,".{}",c)?; }
+        for (name,op) in &self.attrs {
+            match op {
+                Some((op,v)) => write!(f,"[{}{}\"{}\"]",name,op.as_str(),v.replace('\\',"\\\\").replace('"',"\\\""))?,
+                None => write!(f,"[{}]",name)?,
+            }
+        }
+        for p in &self.pseudos { p.fmt(f)?; }
+        Ok(())
+    }
+}
+
+// compound selectors joined by combinators, modifiers apply to the last compound
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Selector {
+    first: Compound,
+    rest: Vec<(Combinator,Compound)>,
+}
+impl Selector {
+    fn from_compound(c: Compound) -> Selector {
+        Selector { first: c, rest: Vec::new() }
+    }
+    pub fn any() -> Selector {
+        Selector::from_compound(Compound::default())
+    }
+    pub fn tag<T: ToString>(tag: T) -> Selector {
+        Selector::from_compound(Compound { tag: Some(tag.to_string()), ..Compound::default() })
+    }
+    pub fn class<T: ToString>(class: T) -> Selector {
+        Selector::any().and_class(class)
+    }
+    pub fn id<T: ToString>(id: T) -> Selector {
+        let mut s = Selector::any();
+        s.last().id = Some(id.to_string());
+        s
+    }
+    fn last(&mut self) -> &mut Compound {
+        match self.rest.last_mut() {
+            Some((_,c)) => c,
+            None => &mut self.first,
+        }
+    }
+    pub fn and_class<T: ToString>(mut self, class: T) -> Selector {
+        self.last().classes.push(class.to_string());
+        self
+    }
+    pub fn attr<T: ToString>(mut self, name: T) -> Selector {
+        self.last().attrs.push((name.to_string(),None));
+        self
+    }
+    pub fn attr_op<N: ToString, V: ToString>(mut self, name: N, op: AttrOp, value: V) -> Selector {
+        self.last().attrs.push((name.to_string(),Some((op,value.to_string()))));
+        self
+    }
+    pub fn pseudo(mut self, p: Pseudo) -> Selector {
+        self.last().pseudos.push(p);
+        self
+    }
+    pub fn hover(self) -> Selector {
+        self.pseudo(Pseudo::Hover)
+    }
+    fn combine(mut self, comb: Combinator, other: Selector) -> Selector {
+        self.rest.push((comb,other.first));
+        self.rest.extend(other.rest);
+        self
+    }
+    pub fn descendant(self, other: Selector) -> Selector {
+        self.combine(Combinator::Descendant,other)
+    }
+    pub fn child(self, other: Selector) -> Selector {
+        self.combine(Combinator::Child,other)
+    }
+    pub fn adjacent(self, other: Selector) -> Selector {
+        self.combine(Combinator::Adjacent,other)
+    }
+    pub fn sibling(self, other: Selector) -> Selector {
+        self.combine(Combinator::Sibling,other)
+    }
+}
+impl Nth {
+    fn parse(s: &str) -> Option<Nth> {
+        let s = s.replace(' ',"");
+        match s.as_str() {
+            "odd" => return Some(Nth::Odd),
+            "even" => return Some(Nth::Even),
+            _ => {},
+        }
+        match s.find('n') {
+            None => s.parse().ok().map(Nth::Index),
+            Some(i) => {
+                let a = match &s[..i] {
+                    "" | "+" => 1,
+                    "-" => -1,
+                    a => a.parse().ok()?,
+                };
+                let b = match &s[i+1..] {
+                    "" => 0,
+                    b => b.parse().ok()?,
+                };
+                Some(Nth::Step(a,b))
+            },
+        }
+    }
+}
+
+impl Pseudo {
+    fn parse(colons: &str, name: &str, arg: Option<&str>) -> Option<Pseudo> {
+        Some(match (colons,name,arg) {
+            (":","hover",None) => Pseudo::Hover,
+            (":","focus",None) => Pseudo::Focus,
+            (":","active",None) => Pseudo::Active,
+            (":","visited",None) => Pseudo::Visited,
+            (":","checked",None) => Pseudo::Checked,
+            (":","disabled",None) => Pseudo::Disabled,
+            (":","first-child",None) => Pseudo::FirstChild,
+            (":","last-child",None) => Pseudo::LastChild,
+            (_,"before",None) => Pseudo::Before,
+            (_,"after",None) => Pseudo::After,
+            (":","nth-child",Some(a)) if Nth::parse(a).is_some() => Pseudo::NthChild(Nth::parse(a)?),
+            (":","nth-of-type",Some(a)) if Nth::parse(a).is_some() => Pseudo::NthOfType(Nth::parse(a)?),
+            (":","not",Some(a)) if Selector::parse(a).is_some() => Pseudo::Not(Selector::parse(a)?),
+            (_,_,Some(a)) => Pseudo::Other(format!("{}{}({})",colons,name,a)),
+            (_,_,None) => Pseudo::Other(format!("{}{}",colons,name)),
+        })
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == '\\'
+}
+
+impl Selector {
+    // one selector of a list: compounds of tag, #id, .class, [attr], :pseudo joined by combinators
+    pub fn parse(s: &str) -> Option<Selector> {
+        let chars = s.trim().chars().collect::<Vec<_>>();
+        let ident = |i: &mut usize| {
+            let start = *i;
+            while *i < chars.len() && is_ident(chars[*i]) { *i += 1; }
+            chars[start..*i].iter().collect::<String>()
+        };
+        let mut parts: Vec<(Option<Combinator>,Compound)> = Vec::new();
+        let mut cur = Compound::default();
+        let mut started = false;
+        let mut comb = None;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() || c == '>' || c == '+' || c == '~' {
+                if started {
+                    parts.push((comb.take(),std::mem::take(&mut cur)));
+                    started = false;
+                    comb = Some(Combinator::Descendant);
+                }
+                match c {
+                    '>' => comb = Some(Combinator::Child),
+                    '+' => comb = Some(Combinator::Adjacent),
+                    '~' => comb = Some(Combinator::Sibling),
+                    _ => {},
+                }
+                i += 1;
+                continue;
+            }
+            if parts.is_empty() && !started && comb.is_some() { return None; }
+            started = true;
+            match c {
+                '*' => i += 1,
+                '.' => { i += 1; let n = ident(&mut i); if n.is_empty() { return None; } cur.classes.push(n); },
+                '#' => { i += 1; let n = ident(&mut i); if n.is_empty() { return None; } cur.id = Some(n); },
+                '[' => {
+                    let end = i + chars[i..].iter().position(|c| *c == ']')?;
+                    let inner = chars[i+1..end].iter().collect::<String>();
+                    let ops = [("~=",AttrOp::Word),("^=",AttrOp::Prefix),("$=",AttrOp::Suffix),("*=",AttrOp::Contains),("=",AttrOp::Equals)];
+                    let attr = match ops.iter().find_map(|(o,op)| inner.find(o).map(|p| (p,o.len(),*op))) {
+                        Some((p,len,op)) => {
+                            let v = inner[p+len..].trim();
+                            let v = v.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+                                .or_else(|| v.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                                .unwrap_or(v);
+                            (inner[..p].trim().to_string(),Some((op,v.replace("\\\"","\"").replace("\\\\","\\"))))
+                        },
+                        None => (inner.trim().to_string(),None),
+                    };
+                    if attr.0.is_empty() { return None; }
+                    cur.attrs.push(attr);
+                    i = end + 1;
+                },
+                ':' => {
+                    let colons = match chars.get(i+1) { Some(':') => "::", _ => ":" };
+                    i += colons.len();
+                    let name = ident(&mut i);
+                    if name.is_empty() { return None; }
+                    let arg = match chars.get(i) {
+                        Some('(') => {
+                            let mut depth = 0;
+                            let start = i + 1;
+                            loop {
+                                match chars.get(i)? {
+                                    '(' => depth += 1,
+                                    ')' => { depth -= 1; if depth == 0 { break; } },
+                                    _ => {},
+                                }
+                                i += 1;
+                            }
+                            i += 1;
+                            Some(chars[start..i-1].iter().collect::<String>())
+                        },
+                        _ => None,
+                    };
+                    cur.pseudos.push(Pseudo::parse(colons,&name,arg.as_deref())?);
+                },
+                c if is_ident(c) => cur.tag = Some(ident(&mut i)),
+                _ => return None,
+            }
+        }
+        if !started {
+            if comb.is_some() || parts.is_empty() { return None; }
+        } else {
+            parts.push((comb,cur));
+        }
+        let mut parts = parts.into_iter();
+        let first = parts.next()?.1;
+        Some(Selector {
+            first,
+            rest: parts.map(|(c,p)| (c.unwrap_or(Combinator::Descendant),p)).collect(),
+        })
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.first.fmt(f)?;
+        for (comb,c) in &self.rest {
+            f.write_str(match comb {
+                Combinator::Descendant => " ",
+                Combinator::Child => " > ",
+                Combinator::Adjacent => " + ",
+                Combinator::Sibling => " ~ ",
+            })?;
+            c.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_decls(f: &mut fmt::Formatter, decls: &[(String,Value)]) -> fmt::Result {
+    f.write_str("{")?;
+    for (p,v) in decls {
+        write!(f," {}: {};",p,v)?;
+    }
+    f.write_str(" }")
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Rule {
+    selectors: Vec<Selector>,
+    decls: Vec<(String,Value)>,
+}
+impl Rule {
+    pub fn new(sel: Selector) -> Rule {
+        Rule { selectors: vec![sel], decls: Vec::new() }
+    }
+    pub fn or(mut self, sel: Selector) -> Rule {
+        self.selectors.push(sel);
+        self
+    }
+    // a repeated property replaces the previous value
+    pub fn decl<P: ToString, V: Into<Value>>(mut self, prop: P, value: V) -> Rule {
+        let (prop,value) = (prop.to_string(),value.into());
+        match self.decls.iter_mut().find(|(p,_)| *p == prop) {
+            Some(d) => d.1 = value,
+            None => self.decls.push((prop,value)),
+        }
+        self
+    }
+}
+impl Rule {
+    fn parse(selectors: &[String], decls: Vec<(String,String)>) -> Option<Rule> {
+        Some(Rule {
+            selectors: selectors.iter().map(|s| Selector::parse(s)).collect::<Option<Vec<_>>>()?,
+            decls: decls.into_iter().map(|(p,v)| (p,Value::parse(&v))).collect(),
+        })
+    }
+    pub fn selectors(&self) -> &[Selector] {
+        &self.selectors
+    }
+    pub fn get(&self, prop: &str) -> Option<&Value> {
+        self.decls.iter().find(|(p,_)| p == prop).map(|(_,v)| v)
+    }
+    fn merge(&mut self, other: Rule) {
+        for (p,v) in other.decls {
+            match self.decls.iter_mut().find(|(q,_)| *q == p) {
+                Some(d) => d.1 = v,
+                None => self.decls.push((p,v)),
+            }
+        }
+    }
+}
+
+fn is_statement(raw: &str) -> bool {
+    ["@charset","@import","@layer"].iter().any(|at| raw.starts_with(at)) && !raw.contains('{')
+}
+
+fn merge_rules(rules: &mut Vec<Rule>, rule: Rule) {
+    match rules.iter_mut().find(|r| r.selectors == rule.selectors) {
+        Some(r) => r.merge(rule),
+        None => rules.push(rule),
+    }
+}
+
+impl From<Style> for Rule {
+    fn from(s: Style) -> Rule {
+        let mut r = Rule::new(Selector::class(s.name));
+        for (o,v) in s.opts {
+            r = r.decl(o,v.into_owned());
+        }
+        r
+    }
+}
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i,s) in self.selectors.iter().enumerate() {
+            if i > 0 { f.write_str(", ")?; }
+            s.fmt(f)?;
+        }
+        f.write_str(" ")?;
+        write_decls(f,&self.decls)
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Media {
+    query: String,
+    rules: Vec<Rule>,
+}
+impl Media {
+    pub fn new<T: ToString>(query: T) -> Media {
+        Media { query: query.to_string(), rules: Vec::new() }
+    }
+    pub fn max_width(width: Length) -> Media {
+        Media::new(format!("(max-width: {})",width))
+    }
+    pub fn min_width(width: Length) -> Media {
+        Media::new(format!("(min-width: {})",width))
+    }
+    pub fn rule(mut self, r: Rule) -> Media {
+        self.rules.push(r);
+        self
+    }
+}
+impl fmt::Display for Media {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f,"@media {} {{",self.query)?;
+        for r in &self.rules {
+            writeln!(f,"    {}",r)?;
+        }
+        f.write_str("}")
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Keyframes {
+    name: String,
+    frames: Vec<(String,Vec<(String,Value)>)>,
+}
+impl Keyframes {
+    pub fn new<T: ToString>(name: T) -> Keyframes {
+        Keyframes { name: name.to_string(), frames: Vec::new() }
+    }
+    // stop is "from", "to" or a percentage
+    pub fn frame<S: ToString, P: ToString, V: Into<Value>>(mut self, stop: S, decls: Vec<(P,V)>) -> Keyframes {
+        self.frames.push((stop.to_string(),decls.into_iter().map(|(p,v)| (p.to_string(),v.into())).collect()));
+        self
+    }
+}
+impl fmt::Display for Keyframes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f,"@keyframes {} {{",self.name)?;
+        for (stop,decls) in &self.frames {
+            write!(f,"    {} ",stop)?;
+            write_decls(f,decls)?;
+            f.write_str("\n")?;
+        }
+        f.write_str("}")
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+enum Item {
+    Rule(Rule),
+    Media(Media),
+    Keyframes(Keyframes),
+    // other @-rules and rules with selectors that are not understood, as is
+    Raw(String),
+}
+
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct Stylesheet {
+    items: Vec<Item>,
+}
+impl Stylesheet {
+    pub fn new() -> Stylesheet {
+        Stylesheet::default()
+    }
+    pub fn rule(mut self, r: Rule) -> Stylesheet {
+        self.items.push(Item::Rule(r));
+        self
+    }
+    pub fn style(self, s: Style) -> Stylesheet {
+        self.rule(s.into())
+    }
+    pub fn media(mut self, m: Media) -> Stylesheet {
+        self.items.push(Item::Media(m));
+        self
+    }
+    pub fn keyframes(mut self, k: Keyframes) -> Stylesheet {
+        self.items.push(Item::Keyframes(k));
+        self
+    }
+}
+impl Stylesheet {
+    // Rules, comments, @media and @keyframes blocks and custom properties (--name: value).
+    // Other @-rules and rules with selectors that are not understood are kept as they are.
+    pub fn parse(css: &str) -> Stylesheet {
+        let mut res = Stylesheet::new();
+        for item in parse_items(css) {
+            match item {
+                CssItem::Rule { selectors, decls } => {
+                    let raw = format!("{} {{ {} }}",selectors.join(", "),decls.iter().map(|(p,v)| format!("{}: {};",p,v)).collect::<Vec<_>>().join(" "));
+                    res.items.push(match Rule::parse(&selectors,decls) {
+                        Some(r) => Item::Rule(r),
+                        None => Item::Raw(raw),
+                    });
+                },
+                CssItem::At(at) => res.items.push(parse_at(at)),
+            }
+        }
+        res
+    }
+    // later declarations override earlier ones of the same selectors and property,
+    // media blocks with the same query and keyframes with the same name are merged the same way
+    pub fn merge(mut self, other: Stylesheet) -> Stylesheet {
+        for item in other.items {
+            match item {
+                Item::Rule(r) => {
+                    match self.items.iter_mut().find_map(|i| match i { Item::Rule(q) if q.selectors == r.selectors => Some(q), _ => None }) {
+                        Some(q) => q.merge(r),
+                        None => self.items.push(Item::Rule(r)),
+                    }
+                },
+                Item::Media(m) => {
+                    match self.items.iter_mut().find_map(|i| match i { Item::Media(q) if q.query == m.query => Some(q), _ => None }) {
+                        Some(q) => for r in m.rules { merge_rules(&mut q.rules,r); },
+                        None => self.items.push(Item::Media(m)),
+                    }
+                },
+                Item::Keyframes(k) => {
+                    match self.items.iter_mut().find_map(|i| match i { Item::Keyframes(q) if q.name == k.name => Some(q), _ => None }) {
+                        Some(q) => *q = k,
+                        None => self.items.push(Item::Keyframes(k)),
+                    }
+                },
+                Item::Raw(r) if self.items.contains(&Item::Raw(r.clone())) => {},
+                // @charset, @import and @layer statements only work ahead of the rules
+                Item::Raw(r) if r.starts_with("@charset") => self.items.insert(0,Item::Raw(r)),
+                Item::Raw(r) if is_statement(&r) => {
+                    let i = self.items.iter().position(|i| !matches!(i,Item::Raw(q) if is_statement(q))).unwrap_or(self.items.len());
+                    self.items.insert(i,Item::Raw(r));
+                },
+                Item::Raw(r) => self.items.push(Item::Raw(r)),
+            }
+        }
+        self
+    }
+    // top level rules with exactly this selector
+    pub fn find(&self, selector: &str) -> Option<&Rule> {
+        let sel = Selector::parse(selector)?;
+        self.items.iter().find_map(|i| match i {
+            Item::Rule(r) if r.selectors.len() == 1 && r.selectors[0] == sel => Some(r),
+            _ => None,
+        })
+    }
+    // the merged declarations of .class as a Style
+    pub fn class_style(&self, class: &str) -> Option<Style> {
+        let r = self.find(&format!(".{}",class))?;
+        let mut s = Style::new(class.to_string());
+        for (p,v) in &r.decls {
+            s = s.opt(p.clone(),v.to_string());
+        }
+        Some(s)
+    }
+}
+
+fn parse_at(at: String) -> Item {
+    let (head,body) = match (at.find('{'),at.rfind('}')) {
+        (Some(o),Some(c)) if o < c => (at[..o].trim(),&at[o+1..c]),
+        _ => return Item::Raw(at),
+    };
+    if let Some(query) = head.strip_prefix("@media") {
+        let mut media = Media::new(query.trim());
+        for item in parse_items(body) {
+            match item {
+                CssItem::Rule { selectors, decls } => match Rule::parse(&selectors,decls) {
+                    Some(r) => merge_rules(&mut media.rules,r),
+                    None => return Item::Raw(at),
+                },
+                CssItem::At(_) => return Item::Raw(at),
+            }
+        }
+        return Item::Media(media);
+    }
+    if let Some(name) = head.strip_prefix("@keyframes") {
+        let mut k = Keyframes::new(name.trim());
+        for item in parse_items(body) {
+            match item {
+                CssItem::Rule { selectors, decls } => k.frames.push((selectors.join(", "),decls.into_iter().map(|(p,v)| (p,Value::parse(&v))).collect())),
+                CssItem::At(_) => return Item::Raw(at),
+            }
+        }
+        return Item::Keyframes(k);
+    }
+    Item::Raw(at)
+}
+
+impl fmt::Display for Stylesheet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for item in &self.items {
+            match item {
+                Item::Rule(r) => writeln!(f,"{}",r)?,
+                Item::Media(m) => writeln!(f,"{}",m)?,
+                Item::Keyframes(k) => writeln!(f,"{}",k)?,
+                Item::Raw(r) => writeln!(f,"{}",r)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stylesheet() {
+        let css = Stylesheet::new()
+            .style(Style::new("job").opt("float","left").opt("width","120px"))
+            .rule(Rule::new(Selector::class("jobs").child(Selector::tag("div").pseudo(Pseudo::NthChild(Nth::Step(2,-1)))))
+                  .or(Selector::class("job").hover())
+                  .decl("padding",vec![Length::px(4),Length::em(0.5)])
+                  .decl("opacity",0.5))
+            .rule(Rule::new(Selector::tag("a").attr_op("href",AttrOp::Prefix,"http\"").descendant(Selector::any().pseudo(Pseudo::Not(Selector::class("x")))))
+                  .decl("grid-template-columns",vec![Length::fr(1),Length::percent(25)]))
+            .media(Media::max_width(Length::px(600)).rule(Rule::new(Selector::id("side").attr("hidden")).decl("display","none")))
+            .keyframes(Keyframes::new("fade").frame("from",vec![("opacity",0)]).frame(Length::percent(100),vec![("opacity",1)]));
+        assert_eq!(css.to_string(),".job { float: left; width: 120px; }
+.jobs > div:nth-child(2n-1), .job:hover { padding: 4px 0.5em; opacity: 0.5; }
+a[href^=\"http\\\"\"] :not(.x) { grid-template-columns: 1fr 25%; }
+@media (max-width: 600px) {
+    #side[hidden] { display: none; }
+}
+@keyframes fade {
+    from { opacity: 0; }
+    100% { opacity: 1; }
+}
+");
+        assert_eq!(Selector::any().to_string(),"*");
+    }
+
+    #[test]
+    fn parse() {
+        for sel in ["div.a.b > span:hover","ul li:nth-child(2n+1)","a[href^=\"http\"] ~ b + i","li :not(.x)","p::before","input:focus-visible","#side[hidden]"] {
+            assert_eq!(Selector::parse(sel).unwrap().to_string(),sel);
+        }
+        assert_eq!(Selector::parse("div  >  .a").unwrap().to_string(),"div > .a");
+        assert_eq!(Selector::parse(":nth-child(odd)").unwrap(),Selector::any().pseudo(Pseudo::NthChild(Nth::Odd)));
+        assert_eq!(Selector::parse("[type=checkbox]").unwrap().to_string(),"[type=\"checkbox\"]");
+        let css = Stylesheet::parse(".icon { background: url(data:image/png;base64,AAAA); } .q::before { content: \"} {\"; color: red; }");
+        assert_eq!(css.find(".icon").unwrap().get("background"),Some(&Value::Keyword("url(data:image/png;base64,AAAA)".to_string())));
+        assert_eq!(css.find(".q::before").unwrap().get("content"),Some(&Value::Keyword("\"} {\"".to_string())));
+        assert_eq!(css.find(".q::before").unwrap().get("color"),Some(&Value::Keyword("red".to_string())));
+        assert!(Selector::parse("> a").is_none());
+        assert!(Selector::parse("a >").is_none());
+        assert!(Selector::parse("a{").is_none());
+
+        let designers = Stylesheet::parse("/* brand */
+:root { --ui-accent: #ff6600; --brand-font: 'Inter', sans-serif; }
+.job { width: 120px; color: red; }
+@import url(extra.css);
+@media (max-width: 600px) { .job { display: none; } }
+@keyframes fade { from { opacity: 0 } to { opacity: 1 } }
+");
+        assert_eq!(designers.find(":root").unwrap().get("--brand-font"),Some(&Value::Keyword("'Inter', sans-serif".to_string())));
+        assert_eq!(designers.find(".job").unwrap().get("width"),Some(&Value::Length(Length::px(120))));
+
+        let merged = Stylesheet::new()
+            .style(Style::new("job").opt("width","100px").opt("float","left"))
+            .media(Media::max_width(Length::px(600)).rule(Rule::new(Selector::class("job")).decl("width","auto")))
+            .merge(designers);
+        assert_eq!(merged.class_style("job").unwrap().to_string(),".job {    width: 120px;\n    float: left;\n    color: red;\n}");
+        assert_eq!(merged.to_string(),"@import url(extra.css);
+.job { width: 120px; float: left; color: red; }
+@media (max-width: 600px) {
+    .job { width: auto; display: none; }
+}
+:root { --ui-accent: #ff6600; --brand-font: 'Inter', sans-serif; }
+@keyframes fade {
+    from { opacity: 0; }
+    to { opacity: 1; }
+}
+");
+        let merged = merged.merge(Stylesheet::parse("@layer base, ui;\n@charset \"utf-8\";\n"));
+        assert!(merged.to_string().starts_with("@charset \"utf-8\";\n@import url(extra.css);\n@layer base, ui;\n.job {"));
+    }
+}
@@ -1,4 +1,9 @@
-use std::collections::BTreeSet;
+use std::{
+    fmt, io,
+    collections::BTreeSet,
+};
+
+use super::{Block,Element,Node,Component,Dependency,escape,write_escaped,fnv64,IoFmt,form::encode,css::optimize_css};
 
 #[derive(Debug,Clone,Copy,Ord,PartialOrd,Eq,PartialEq)]
 pub struct RowRef {
@@ -14,8 +19,167 @@ pub struct TableRef {
 #[derive(Debug)]
 struct Row {
     styles: String,
-    divs: Vec<String>,
-    args: Option<Vec<usize>>,
+    template: Vec<Segment>,
+    boxes: Vec<BoxGeometry>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Static(String),
+    Value(usize),
+    // " title='..'" of the value, inside the opening tag of an ellipsis cell
+    Title(usize),
+}
+
+// the last div closes the row, every other one is followed by its value
+fn compile(divs: Vec<String>, args: Option<&[usize]>, overflow: &[Overflow]) -> Vec<Segment> {
+    let mut res = Vec::new();
+    let push = |seg: &str, res: &mut Vec<Segment>| match res.last_mut() {
+        Some(Segment::Static(s)) => *s += seg,
+        _ => res.push(Segment::Static(seg.to_string())),
+    };
+    let cnt = divs.len().saturating_sub(1);
+    for (i,d) in divs.iter().enumerate() {
+        let arg = match args {
+            None => Some(i),
+            Some(args) => args.get(i).copied(),
+        };
+        match arg {
+            Some(arg) if i < cnt => {
+                match overflow.get(arg) {
+                    Some(Overflow::Ellipsis) if d.ends_with('>') => {
+                        push(&d[.. d.len()-1],&mut res);
+                        res.push(Segment::Title(arg));
+                        push(">",&mut res);
+                    },
+                    _ => push(d,&mut res),
+                }
+                res.push(Segment::Value(arg));
+            },
+            _ => push(d,&mut res),
+        }
+    }
+    res
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+impl Side {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum BoxWidth {
+    Fixed(usize),
+    Min(usize),
+    Auto,
+}
+
+// one generated div, boxes are listed in document order, depth 0 is a direct child of the row div
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct BoxGeometry {
+    pub class: String,
+    pub depth: usize,
+    pub arg: Option<usize>,
+    pub padding_left: usize,
+    pub padding_right: usize,
+    pub width: BoxWidth,
+    pub margin: Option<(Side,usize)>,
+    pub float: Option<Side>,
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct RowGeometry {
+    pub table: String,
+    pub soft: bool,
+    pub width: usize,
+    pub boxes: Vec<BoxGeometry>,
+}
+impl RowGeometry {
+    pub fn cells(&self) -> impl Iterator<Item = &BoxGeometry> {
+        self.boxes.iter().filter(|b| b.arg.is_some())
+    }
+}
+
+#[derive(Debug,Default)]
+struct RowParts {
+    styles: String,
+    pads: Vec<String>,
+    args: Vec<usize>,
+    boxes: Vec<BoxGeometry>,
+    depth: usize,
+}
+impl RowParts {
+    fn cell(&mut self, b: BoxGeometry) {
+        self.pads.push(b.class.clone());
+        if let Some(arg) = b.arg { self.args.push(arg); }
+        self.boxes.push(b);
+    }
+    fn wrapper(&mut self, b: BoxGeometry) {
+        self.boxes.push(b);
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Overflow {
+    #[default]
+    Clip,
+    Ellipsis,
+    Wrap,
+    BreakAll,
+}
+impl Overflow {
+    fn css(&self) -> Option<&'static str> {
+        match self {
+            Overflow::Clip => None,
+            Overflow::Ellipsis => Some("overflow: hidden; white-space: nowrap; text-overflow: ellipsis;"),
+            Overflow::Wrap => Some("white-space: normal; overflow-wrap: break-word;"),
+            Overflow::BreakAll => Some("white-space: normal; word-break: break-all;"),
+        }
+    }
+}
+
+// cells are (class, value index)
+fn overflow_styles(cells: &[(String,usize)], overflow: &[Overflow]) -> String {
+    let mut res = String::new();
+    for mode in [Overflow::Ellipsis,Overflow::Wrap,Overflow::BreakAll] {
+        let classes = cells.iter()
+            .filter(|(_,arg)| overflow.get(*arg) == Some(&mode))
+            .map(|(cls,_)| format!(".{}",cls))
+            .collect::<Vec<_>>();
+        if let (false,Some(css)) = (classes.is_empty(),mode.css()) {
+            res += &format!("{} {{ {} }}\n",classes.join(", "),css);
+        }
+    }
+    res
+}
+
+fn write_title<W: fmt::Write + ?Sized>(w: &mut W, html: &str) -> fmt::Result {
+    let mut tag = false;
+    let mut last = 0;
+    for (i,c) in html.char_indices() {
+        let rep = match (tag,c) {
+            (false,'<') => { tag = true; "" },
+            (true,'>') => { tag = false; last = i + 1; continue },
+            (true,_) => continue,
+            (false,'\'') => "&#39;",
+            (false,'"') => "&quot;",
+            (false,_) => continue,
+        };
+        w.write_str(&html[last .. i])?;
+        w.write_str(rep)?;
+        last = i + 1;
+    }
+    if !tag { w.write_str(&html[last ..])?; }
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -25,7 +189,15 @@ pub enum TableError {
     SoftOnFixed(TableRef),
     MustBeOneSoftColumn(String),
     UnknownTable(TableRef),
+    InvalidUid(String),
+    DuplicateUid(String),
+    UidCollision(String,String),
     UnknownRow(RowRef),
+    Fmt(fmt::Error),
+    Io(io::Error),
+    SelectionAfterRows(TableRef),
+    NoSelection(TableRef),
+    NoEditEndpoint(TableRef),
     FixedRowTooLong {
         table: String,
         width: usize,
@@ -41,7 +213,382 @@ pub enum TableError {
 
 #[derive(Debug,Default)]
 pub struct TableDrawer {
-    row_set: BTreeSet<RowRef>
+    row_set: BTreeSet<RowRef>,
+    row_actions: bool,
+    selection: bool,
+    editing: bool,
+}
+impl TableDrawer {
+    pub fn new() -> TableDrawer {
+        TableDrawer::default()
+    }
+    // for rows written later, so their styles are known beforehand
+    pub fn register(&mut self, row: RowRef) {
+        self.row_set.insert(row);
+    }
+}
+
+#[derive(Debug,Clone)]
+pub enum RowDetail {
+    Block(Block),
+    Url(String),
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct RowOptions {
+    href: Option<String>,
+    id: Option<String>,
+    data: Vec<(String,String)>,
+    detail: Option<RowDetail>,
+    key: Option<String>,
+    select_all: bool,
+}
+impl RowOptions {
+    pub fn new() -> RowOptions {
+        RowOptions::default()
+    }
+    pub fn href<T: ToString>(mut self, href: T) -> RowOptions {
+        self.href = Some(href.to_string());
+        self
+    }
+    pub fn id<T: ToString>(mut self, id: T) -> RowOptions {
+        self.id = Some(id.to_string());
+        self
+    }
+    // a key without any valid character is skipped
+    pub fn data<K: ToString, V: ToString>(mut self, key: K, value: V) -> RowOptions {
+        let key = key.to_string().chars().filter(|c| c.is_ascii_alphanumeric() || (*c == '-') || (*c == '_')).collect::<String>();
+        if !key.is_empty() {
+            self.data.push((key.to_ascii_lowercase(),value.to_string()));
+        }
+        self
+    }
+    // click on the row toggles the pane, so it takes precedence over href
+    pub fn detail(mut self, block: Block) -> RowOptions {
+        self.detail = Some(RowDetail::Block(block));
+        self
+    }
+    pub fn detail_url<T: ToString>(mut self, url: T) -> RowOptions {
+        self.detail = Some(RowDetail::Url(url.to_string()));
+        self
+    }
+
+    // selectable tables only: the row checkbox value
+    pub fn key<T: ToString>(mut self, key: T) -> RowOptions {
+        self.key = Some(key.to_string());
+        self
+    }
+    // selectable tables only: the row gets a select-all checkbox (header row)
+    pub fn select_all(mut self) -> RowOptions {
+        self.select_all = true;
+        self
+    }
+
+    fn write_checkbox<W: fmt::Write + ?Sized>(&self, w: &mut W, sel: &Selection) -> fmt::Result {
+        match (&self.key, self.select_all) {
+            (_, true) => {
+                w.write_str("<input type='checkbox' data-select-all='")?;
+                write_escaped(w,&sel.form)?;
+                w.write_str("'>")
+            },
+            (Some(key), false) => {
+                w.write_str("<input type='checkbox' form='")?;
+                write_escaped(w,&sel.form)?;
+                w.write_str("' name='")?;
+                write_escaped(w,&sel.name)?;
+                w.write_str("' value='")?;
+                write_escaped(w,key)?;
+                w.write_str("' data-select='")?;
+                write_escaped(w,&sel.form)?;
+                w.write_str("'>")
+            },
+            (None, false) => w.write_str("&nbsp;"),
+        }
+    }
+    fn has_actions(&self) -> bool {
+        self.href.is_some() || self.detail.is_some()
+    }
+    fn write_open<W: fmt::Write + ?Sized>(&self, w: &mut W, class: &str) -> fmt::Result {
+        w.write_str("<div class='")?;
+        w.write_str(class)?;
+        if self.has_actions() { w.write_str(" row_link")?; }
+        w.write_str("'")?;
+        if let Some(id) = &self.id {
+            w.write_str(" id='")?;
+            write_escaped(w,id)?;
+            w.write_str("'")?;
+        }
+        for (k,v) in &self.data {
+            w.write_str(" data-")?;
+            w.write_str(k)?;
+            w.write_str("='")?;
+            write_escaped(w,v)?;
+            w.write_str("'")?;
+        }
+        if let Some(href) = &self.href {
+            w.write_str(" data-row-href='")?;
+            write_escaped(w,href)?;
+            w.write_str("'")?;
+        }
+        if self.detail.is_some() {
+            w.write_str(" data-row-detail='1'")?;
+        }
+        w.write_str(">\n")
+    }
+    fn write_detail<W: fmt::Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+        match &self.detail {
+            None => Ok(()),
+            Some(RowDetail::Block(b)) => write!(w,"<div class='row_detail' hidden>\n{}\n</div>\n",b),
+            Some(RowDetail::Url(url)) => {
+                w.write_str("<div class='row_detail' data-detail-url='")?;
+                write_escaped(w,url)?;
+                w.write_str("' hidden></div>\n")
+            },
+        }
+    }
+}
+
+fn write_template<W: fmt::Write + ?Sized, S: AsRef<str>>(w: &mut W, row: &Row, class: &str, options: &RowOptions, sel: Option<&Selection>, values: &[S]) -> fmt::Result {
+    options.write_open(w,class)?;
+    for seg in &row.template {
+        let arg = match seg {
+            Segment::Static(s) => { w.write_str(s)?; continue; },
+            Segment::Value(arg) | Segment::Title(arg) => *arg,
+        };
+        // with selection the first value is the checkbox
+        let value = match (sel,arg) {
+            (Some(sel),0) => match seg {
+                Segment::Value(_) => { options.write_checkbox(w,sel)?; continue; },
+                _ => continue,
+            },
+            (Some(_),arg) => values.get(arg - 1),
+            (None,arg) => values.get(arg),
+        };
+        let value = value.map(|v| v.as_ref()).unwrap_or("&nbsp;");
+        match seg {
+            Segment::Title(_) => {
+                w.write_str(" title='")?;
+                write_title(w,value)?;
+                w.write_str("'")?;
+            },
+            _ => w.write_str(value)?,
+        }
+    }
+    w.write_str("</div>")?;
+    if options.detail.is_some() {
+        w.write_str("\n")?;
+        options.write_detail(w)?;
+    }
+    Ok(())
+}
+
+const ROW_ACTIONS_CSS: &str = ".row_link { cursor: pointer; }
+.row_link:hover { background: var(--ui-row-hover, transparent); }
+.row_detail { clear: both; overflow: hidden; background: var(--ui-surface, transparent); }
+";
+
+const ROW_ACTIONS_JS: &str = "document.addEventListener('click', function(e) {
+    if (e.target.closest('a,button,input,select,textarea,.row_detail,.cell_edit')) { return; }
+    var row = e.target.closest('[data-row-href],[data-row-detail]');
+    if (!row) { return; }
+    if (row.hasAttribute('data-row-detail')) {
+        var d = row.nextElementSibling;
+        if (!d || !d.classList.contains('row_detail')) { return; }
+        var url = d.getAttribute('data-detail-url');
+        if (url && d.hidden && !d.hasAttribute('data-loaded')) {
+            d.setAttribute('data-loaded','1');
+            d.textContent = '...';
+            fetch(url).then(function(r) {
+                if (!r.ok) { throw new Error(r.status + ' ' + r.statusText); }
+                return r.text();
+            }).then(function(t) { d.innerHTML = t; }).catch(function(err) {
+                d.removeAttribute('data-loaded');
+                d.textContent = err;
+            });
+        }
+        d.hidden = !d.hidden;
+        return;
+    }
+    var href = row.getAttribute('data-row-href');
+    if (e.ctrlKey || e.metaKey) { window.open(href); } else { window.location.href = href; }
+});
+";
+
+const SELECTION_CSS: &str = ".bulk_actions { clear: both; padding: var(--ui-spacing, 4px) 0; border-top: 1px solid var(--ui-border, #ccc); }
+.bulk_actions button:disabled { opacity: 0.5; }
+";
+
+const SELECTION_JS: &str = "function bulkUpdate(form) {
+    var n = document.querySelectorAll('input[data-select=\"' + form + '\"]:checked').length;
+    document.querySelectorAll('[data-select-count=\"' + form + '\"]').forEach(function(c) { c.textContent = n; });
+    var f = document.getElementById(form);
+    if (f) { f.querySelectorAll('button[name=action]').forEach(function(b) { b.disabled = (n == 0); }); }
+}
+document.addEventListener('change', function(e) {
+    var all = e.target.getAttribute('data-select-all');
+    if (all !== null) {
+        document.querySelectorAll('input[data-select=\"' + all + '\"]').forEach(function(c) { c.checked = e.target.checked; });
+        bulkUpdate(all);
+        return;
+    }
+    var form = e.target.getAttribute('data-select');
+    if (form !== null) {
+        document.querySelectorAll('input[data-select-all=\"' + form + '\"]').forEach(function(c) { c.checked = false; });
+        bulkUpdate(form);
+    }
+});
+document.addEventListener('DOMContentLoaded', function() {
+    document.querySelectorAll('form.bulk_actions').forEach(function(f) { bulkUpdate(f.id); });
+});
+";
+
+#[derive(Debug,Clone)]
+pub struct Selection {
+    form: String,
+    action: String,
+    name: String,
+    width: usize,
+    actions: Vec<(String,String)>,
+}
+impl Selection {
+    pub fn new<F: ToString, A: ToString>(form: F, action: A) -> Selection {
+        Selection {
+            form: form.to_string(),
+            action: action.to_string(),
+            name: "keys".to_string(),
+            width: 16,
+            actions: Vec::new(),
+        }
+    }
+    pub fn name<T: ToString>(mut self, name: T) -> Selection {
+        self.name = name.to_string();
+        self
+    }
+    pub fn width(mut self, width: usize) -> Selection {
+        self.width = width;
+        self
+    }
+    pub fn action<V: ToString, L: ToString>(mut self, value: V, label: L) -> Selection {
+        self.actions.push((value.to_string(),label.to_string()));
+        self
+    }
+}
+
+const EDIT_CSS: &str = ".cell_edit { cursor: text; border-bottom: 1px dotted; }
+.cell_edit input, .cell_edit select { width: 100%; box-sizing: border-box; }
+.cell_saving { opacity: 0.5; }
+.cell_error { color: var(--ui-danger, #c00); border-bottom: 1px solid var(--ui-danger, #c00); }
+";
+
+const EDIT_JS: &str = "function cellEditDone(cell, text, cls, title) {
+    cell.removeAttribute('data-editing');
+    cell.classList.remove('cell_saving');
+    cell.classList.remove('cell_saved');
+    cell.classList.remove('cell_error');
+    if (cls) { cell.classList.add(cls); }
+    if (title) { cell.title = title; } else { cell.removeAttribute('title'); }
+    cell.textContent = text;
+}
+function cellEditSave(cell, input) {
+    var value = input.value;
+    var old = cell.getAttribute('data-edit-value');
+    var shown = cell.getAttribute('data-edit-shown');
+    if (value == old) { cellEditDone(cell, shown, null, null); return; }
+    var label = (input.tagName == 'SELECT') ? input.options[input.selectedIndex].text : value;
+    cell.classList.add('cell_saving');
+    var body = new URLSearchParams();
+    body.append('key', cell.getAttribute('data-edit-key'));
+    body.append('field', cell.getAttribute('data-edit-field'));
+    body.append('value', value);
+    fetch(cell.getAttribute('data-edit-url'), { method: 'POST', body: body }).then(function(r) {
+        return r.text().then(function(t) {
+            if (!r.ok) { throw new Error(t || (r.status + ' ' + r.statusText)); }
+            cell.setAttribute('data-edit-value', value);
+            cell.setAttribute('data-edit-shown', t || label);
+            cellEditDone(cell, t || label, 'cell_saved', null);
+        });
+    }).catch(function(err) {
+        cellEditDone(cell, shown, 'cell_error', err.message);
+    });
+}
+document.addEventListener('click', function(e) {
+    var cell = e.target.closest('.cell_edit');
+    if (!cell || cell.hasAttribute('data-editing')) { return; }
+    cell.setAttribute('data-editing', '1');
+    cell.setAttribute('data-edit-shown', cell.textContent);
+    var kind = cell.getAttribute('data-edit-kind');
+    var input;
+    if (kind == 'select') {
+        input = document.createElement('select');
+        new URLSearchParams(cell.getAttribute('data-edit-options')).forEach(function(label, value) {
+            var o = document.createElement('option');
+            o.value = value;
+            o.text = label;
+            input.appendChild(o);
+        });
+        input.addEventListener('change', function() { cellEditSave(cell, input); });
+    } else {
+        input = document.createElement('input');
+        input.type = kind;
+    }
+    input.value = cell.getAttribute('data-edit-value');
+    input.addEventListener('keydown', function(k) {
+        if (k.key == 'Enter') { k.preventDefault(); cellEditSave(cell, input); }
+        if (k.key == 'Escape') { cellEditDone(cell, cell.getAttribute('data-edit-shown'), null, null); }
+    });
+    input.addEventListener('blur', function() {
+        if (cell.hasAttribute('data-editing') && !cell.classList.contains('cell_saving')) { cellEditSave(cell, input); }
+    });
+    cell.textContent = '';
+    cell.appendChild(input);
+    input.focus();
+});
+";
+
+#[derive(Debug,Clone)]
+pub enum EditKind {
+    Text,
+    Number,
+    Select(Vec<(String,String)>),
+}
+
+#[derive(Debug,Clone)]
+pub struct EditableCell {
+    key: String,
+    field: String,
+    kind: EditKind,
+    value: String,
+}
+impl EditableCell {
+    pub fn text<K: ToString, F: ToString, V: ToString>(key: K, field: F, value: V) -> EditableCell {
+        EditableCell { key: key.to_string(), field: field.to_string(), kind: EditKind::Text, value: value.to_string() }
+    }
+    pub fn number<K: ToString, F: ToString, V: ToString>(key: K, field: F, value: V) -> EditableCell {
+        EditableCell { key: key.to_string(), field: field.to_string(), kind: EditKind::Number, value: value.to_string() }
+    }
+    // options are (value, label) pairs
+    pub fn select<K: ToString, F: ToString, V: ToString>(key: K, field: F, value: V, options: Vec<(String,String)>) -> EditableCell {
+        EditableCell { key: key.to_string(), field: field.to_string(), kind: EditKind::Select(options), value: value.to_string() }
+    }
+
+    fn render(&self, url: &str) -> String {
+        let (kind,shown,options) = match &self.kind {
+            EditKind::Text => ("text",self.value.as_str(),None),
+            EditKind::Number => ("number",self.value.as_str(),None),
+            EditKind::Select(opts) => {
+                let shown = opts.iter().find(|(v,_)| *v == self.value).map(|(_,l)| l.as_str()).unwrap_or(self.value.as_str());
+                let options = opts.iter().map(|(v,l)| format!("{}={}",encode(v),encode(l))).collect::<Vec<_>>().join("&");
+                ("select",shown,Some(options))
+            },
+        };
+        let mut res = format!("<span class='cell_edit' data-edit-url='{}' data-edit-key='{}' data-edit-field='{}' data-edit-kind='{}' data-edit-value='{}'",
+                              escape(url),escape(&self.key),escape(&self.field),kind,escape(&self.value));
+        if let Some(options) = options {
+            res += &format!(" data-edit-options='{}'",escape(&options));
+        }
+        res += &format!(">{}</span>",escape(shown));
+        res
+    }
 }
 
 #[derive(Debug,Clone,Copy)]
@@ -54,96 +601,221 @@ enum TableType {
 struct TableConf {
     index: usize,
     uid: String,
+    prefix: String,
     tp: TableType,
     half_padding: usize,
+    selection: Option<Selection>,
+    edit_endpoint: Option<String>,
     rows: Vec<Row>,
 }
 
-#[derive(Debug)]
+impl TableConf {
+    fn overflow(&self, overflow: &[Overflow]) -> Vec<Overflow> {
+        let mut res = Vec::new();
+        if self.selection.is_some() { res.push(Overflow::Clip); }
+        res.extend_from_slice(overflow);
+        res
+    }
+}
+
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub enum ClassNaming {
+    // classes start with the table uid
+    #[default]
+    Plain,
+    // "{scope}-{uid}"
+    Scoped(String),
+    // "t{hash}" of the scope and the uid, stable between runs
+    Hashed(String),
+}
+impl ClassNaming {
+    fn prefix(&self, uid: &str) -> String {
+        match self {
+            ClassNaming::Plain => uid.to_string(),
+            ClassNaming::Scoped(scope) => format!("{}-{}",scope,uid),
+            ClassNaming::Hashed(scope) => {
+                let h = fnv64(format!("{}/{}",scope,uid).as_bytes());
+                format!("t{:08x}",(h ^ (h >> 32)) as u32)
+            },
+        }
+    }
+}
+
+fn valid_uid(uid: &str) -> bool {
+    let mut chars = uid.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || (c == '_') => chars.all(|c| c.is_ascii_alphanumeric() || (c == '_') || (c == '-')),
+        _ => false,
+    }
+}
+
+// all generated classes are "{prefix}_r{row}..."
+fn prefixes_collide(a: &str, b: &str) -> bool {
+    let ext = |a: &str, b: &str| match b.strip_prefix(a).and_then(|s| s.strip_prefix("_r")) {
+        Some(s) => s.starts_with(|c: char| c.is_ascii_digit()),
+        None => false,
+    };
+    (a == b) || ext(a,b) || ext(b,a)
+}
+
+#[derive(Debug,Default)]
 pub struct TableBuilder {
+    naming: ClassNaming,
     tables: Vec<TableConf>,
 }
 impl TableBuilder {
     pub fn new() -> TableBuilder {
         TableBuilder {
+            naming: ClassNaming::Plain,
             tables: Vec::new(),
         }
     }
-    pub fn table_fixed<S: ToString>(&mut self, uid: S, width: usize) -> TableRef {
-        let res = TableRef { table_idx: self.tables.len() };
-        self.tables.push(TableConf {
-            index: res.table_idx,
-            uid: uid.to_string(),
-            tp: TableType::Fixed(width),
-            half_padding: 2,
-            rows: Vec::new(),
-        });
-        res
+    pub fn with_naming(mut self, naming: ClassNaming) -> TableBuilder {
+        self.naming = naming;
+        self
     }
-    pub fn table_soft<S: ToString>(&mut self, uid: S, min_width: usize) -> TableRef {
+    fn table<S: ToString>(&mut self, uid: S, tp: TableType) -> Result<TableRef,TableError> {
+        let uid = uid.to_string();
+        let scope_ok = match &self.naming {
+            ClassNaming::Plain => true,
+            ClassNaming::Scoped(scope) | ClassNaming::Hashed(scope) => valid_uid(scope),
+        };
+        if !valid_uid(&uid) || !scope_ok { return Err(TableError::InvalidUid(uid)); }
+        let prefix = self.naming.prefix(&uid);
+        for t in &self.tables {
+            if t.uid == uid { return Err(TableError::DuplicateUid(uid)); }
+            if prefixes_collide(&t.prefix,&prefix) { return Err(TableError::UidCollision(t.uid.clone(),uid)); }
+        }
         let res = TableRef { table_idx: self.tables.len() };
         self.tables.push(TableConf {
             index: res.table_idx,
-            uid: uid.to_string(),
-            tp: TableType::Soft(min_width),
+            uid, prefix, tp,
             half_padding: 2,
+            selection: None,
+            edit_endpoint: None,
             rows: Vec::new(),
         });
-        res
+        Ok(res)
+    }
+    pub fn table_fixed<S: ToString>(&mut self, uid: S, width: usize) -> Result<TableRef,TableError> {
+        self.table(uid,TableType::Fixed(width))
+    }
+    pub fn table_soft<S: ToString>(&mut self, uid: S, min_width: usize) -> Result<TableRef,TableError> {
+        self.table(uid,TableType::Soft(min_width))
+    }
+    // tables of both builders can be drawn on the same page
+    pub fn check_compatible(&self, other: &TableBuilder) -> Result<(),TableError> {
+        for a in &self.tables {
+            for b in &other.tables {
+                if prefixes_collide(&a.prefix,&b.prefix) { return Err(TableError::UidCollision(a.uid.clone(),b.uid.clone())); }
+            }
+        }
+        Ok(())
     }
     pub fn with_half_padding(&mut self, table: TableRef, hp: usize) -> Result<(),TableError> {
         if self.tables.len() <= table.table_idx { return Err(TableError::UnknownTable(table)); }
         self.tables[table.table_idx].half_padding = hp;
         Ok(())
     }
+    pub fn with_edit_endpoint<T: ToString>(&mut self, table: TableRef, url: T) -> Result<(),TableError> {
+        if self.tables.len() <= table.table_idx { return Err(TableError::UnknownTable(table)); }
+        self.tables[table.table_idx].edit_endpoint = Some(url.to_string());
+        Ok(())
+    }
+    pub fn with_selection(&mut self, table: TableRef, selection: Selection) -> Result<(),TableError> {
+        if self.tables.len() <= table.table_idx { return Err(TableError::UnknownTable(table)); }
+        if !self.tables[table.table_idx].rows.is_empty() { return Err(TableError::SelectionAfterRows(table)); }
+        self.tables[table.table_idx].selection = Some(selection);
+        Ok(())
+    }
 
     pub fn create_row_fixed(&mut self, table_ref: TableRef, columns: &[Option<usize>]) -> Result<RowRef,TableError> {
+        self.create_row_fixed_with(table_ref,columns,&[])
+    }
+
+    // overflow is given per column, missing columns are clipped
+    pub fn create_row_fixed_with(&mut self, table_ref: TableRef, columns: &[Option<usize>], overflow: &[Overflow]) -> Result<RowRef,TableError> {
         if self.tables.len() <= table_ref.table_idx { return Err(TableError::UnknownTable(table_ref)); }
         let table_idx = table_ref.table_idx;
         
+        let mut cols = Vec::new();
+        let columns = match &self.tables[table_idx].selection {
+            None => columns,
+            Some(sel) => {
+                cols.push(Some(sel.width));
+                cols.extend_from_slice(columns);
+                &cols
+            },
+        };
+        let overflow = self.tables[table_idx].overflow(overflow);
         match self.tables[table_idx].tp {
-            TableType::Fixed(width) => create_fixed(&mut self.tables[table_idx],width,columns),
+            TableType::Fixed(width) => create_fixed(&mut self.tables[table_idx],width,columns,overflow),
             TableType::Soft(_) => Err(TableError::FixedOnSoft(table_ref)),
         }
     }
 
     pub fn create_row_soft(&mut self, table_ref: TableRef, columns: Vec<SoftColumn>) -> Result<RowRef,TableError> {
+        self.create_row_soft_with(table_ref,columns,&[])
+    }
+
+    // overflow is given per subcolumn in order, missing subcolumns are clipped
+    pub fn create_row_soft_with(&mut self, table_ref: TableRef, mut columns: Vec<SoftColumn>, overflow: &[Overflow]) -> Result<RowRef,TableError> {
         if self.tables.len() <= table_ref.table_idx { return Err(TableError::UnknownTable(table_ref)); }
         let table_idx = table_ref.table_idx;
+        if let (Some(sel),Some(first)) = (&self.tables[table_idx].selection,columns.first_mut()) {
+            first.subcolumns.insert(0,Some(sel.width));
+        }
+        let overflow = self.tables[table_idx].overflow(overflow);
         
         match self.tables[table_idx].tp {
             TableType::Fixed(_) => Err(TableError::SoftOnFixed(table_ref)),
-            TableType::Soft(min_width) => create_soft(&mut self.tables[table_idx],min_width,columns),
+            TableType::Soft(min_width) => create_soft(&mut self.tables[table_idx],min_width,columns,overflow),
         }
     }
 
-    pub fn row(&self, row_ref: RowRef, class: &str, mut values: Vec<String>, drawer: &mut TableDrawer) -> Result<String,TableError> {
+    pub fn row(&self, row_ref: RowRef, class: &str, values: Vec<String>, drawer: &mut TableDrawer) -> Result<String,TableError> {
+        self.row_with(row_ref,class,&RowOptions::default(),values,drawer)
+    }
+
+    pub fn row_with(&self, row_ref: RowRef, class: &str, options: &RowOptions, values: Vec<String>, drawer: &mut TableDrawer) -> Result<String,TableError> {
+        // the static markup and the values, to allocate once
+        let size = self.tables.get(row_ref.table_idx).and_then(|t| t.rows.get(row_ref.row_idx)).map(|r| {
+            r.template.iter().map(|seg| match seg {
+                Segment::Static(s) => s.len(),
+                _ => 0,
+            }).sum::<usize>()
+        }).unwrap_or(0);
+        let mut res = String::with_capacity(size + class.len() + 32 + values.iter().map(|v| v.len()).sum::<usize>());
+        self.write_row(&mut res,row_ref,class,options,&values,drawer)?;
+        Ok(res)
+    }
+
+    // streams the row from its precompiled template, missing values are rendered as &nbsp;
+    pub fn write_row<W: fmt::Write + ?Sized, S: AsRef<str>>(&self, w: &mut W, row_ref: RowRef, class: &str, options: &RowOptions, values: &[S], drawer: &mut TableDrawer) -> Result<(),TableError> {
+        self.prepare_row(row_ref,options,drawer)?;
+        let table = &self.tables[row_ref.table_idx];
+        let row = &table.rows[row_ref.row_idx];
+        if row.template.is_empty() { return Ok(()); }
+        write_template(w,row,class,options,table.selection.as_ref(),values).map_err(TableError::Fmt)
+    }
+    // records the styles and scripts the row needs, for rows written later
+    pub fn prepare_row(&self, row_ref: RowRef, options: &RowOptions, drawer: &mut TableDrawer) -> Result<(),TableError> {
         if self.tables.len() <= row_ref.table_idx { return Err(TableError::UnknownRow(row_ref)); }
-        if self.tables[row_ref.table_idx].rows.len() <= row_ref.row_idx { return Err(TableError::UnknownRow(row_ref)); }
-        let row = &self.tables[row_ref.table_idx].rows[row_ref.row_idx];
+        let table = &self.tables[row_ref.table_idx];
+        if table.rows.len() <= row_ref.row_idx { return Err(TableError::UnknownRow(row_ref)); }
         drawer.row_set.insert(row_ref);
-        if row.divs.len() == 0 { return Ok(String::new()); }
-        let cnt = row.divs.len() - 1;
-        if cnt > values.len() {
-            let i = values.len();
-            for _ in  i .. cnt {
-                values.push("&nbsp;".to_string());
-            }
-        }
-        let mut res = format!("<div class='{}'>\n",class);
-        for (i,d) in row.divs.iter().enumerate() {
-            res += d;
-            if i < values.len() {
-                match &row.args {
-                    None => res += &values[i],
-                    Some(args) => if (i < args.len()) && (args[i] < values.len()) {
-                        res += &values[args[i]];
-                    },
-                }
-            }
+        if table.rows[row_ref.row_idx].template.is_empty() { return Ok(()); }
+        if options.has_actions() { drawer.row_actions = true; }
+        if table.selection.is_some() { drawer.selection = true; }
+        Ok(())
+    }
+
+    pub fn write_row_io<W: io::Write + ?Sized, S: AsRef<str>>(&self, w: &mut W, row_ref: RowRef, class: &str, options: &RowOptions, values: &[S], drawer: &mut TableDrawer) -> Result<(),TableError> {
+        let mut w = IoFmt::new(w);
+        match self.write_row(&mut w,row_ref,class,options,values,drawer) {
+            Err(TableError::Fmt(e)) => Err(w.error.take().map(TableError::Io).unwrap_or(TableError::Fmt(e))),
+            r => r,
         }
-        res += "</div>";
-        Ok(res)
     }
 
     pub fn styles(&self, drawer: &TableDrawer) -> String {
@@ -153,8 +825,170 @@ impl TableBuilder {
             if self.tables[rr.table_idx].rows.len() <= rr.row_idx { continue; }
             res += &self.tables[rr.table_idx].rows[rr.row_idx].styles;
         }
+        if drawer.row_actions {
+            res += ROW_ACTIONS_CSS;
+        }
+        if drawer.selection {
+            res += SELECTION_CSS;
+        }
+        if drawer.editing {
+            res += EDIT_CSS;
+        }
+        res
+    }
+
+    pub fn editable(&self, table: TableRef, cell: &EditableCell, drawer: &mut TableDrawer) -> Result<String,TableError> {
+        if self.tables.len() <= table.table_idx { return Err(TableError::UnknownTable(table)); }
+        match &self.tables[table.table_idx].edit_endpoint {
+            Some(url) => {
+                drawer.editing = true;
+                Ok(cell.render(url))
+            },
+            None => Err(TableError::NoEditEndpoint(table)),
+        }
+    }
+
+    pub fn bulk_actions(&self, table: TableRef, drawer: &mut TableDrawer) -> Result<String,TableError> {
+        if self.tables.len() <= table.table_idx { return Err(TableError::UnknownTable(table)); }
+        let sel = match &self.tables[table.table_idx].selection {
+            Some(sel) => sel,
+            None => return Err(TableError::NoSelection(table)),
+        };
+        drawer.selection = true;
+        let form = escape(&sel.form);
+        let mut res = format!("<form id='{}' class='bulk_actions' method='post' action='{}'>\n",form,escape(&sel.action));
+        for (value,label) in &sel.actions {
+            res += &format!("<button type='submit' name='action' value='{}'>{}</button>\n",escape(value),escape(label));
+        }
+        res += &format!("<span class='bulk_count' data-select-count='{}'>0</span>\n</form>",form);
+        Ok(res)
+    }
+
+    pub fn optimized_styles(&self, drawer: &TableDrawer, minify: bool) -> String {
+        optimize_css(&self.styles(drawer),minify)
+    }
+
+    pub fn geometry(&self, row_ref: RowRef) -> Result<RowGeometry,TableError> {
+        if self.tables.len() <= row_ref.table_idx { return Err(TableError::UnknownRow(row_ref)); }
+        let table = &self.tables[row_ref.table_idx];
+        if table.rows.len() <= row_ref.row_idx { return Err(TableError::UnknownRow(row_ref)); }
+        let (soft,width) = match table.tp {
+            TableType::Fixed(w) => (false,w),
+            TableType::Soft(w) => (true,w),
+        };
+        Ok(RowGeometry {
+            table: table.uid.clone(),
+            soft, width,
+            boxes: table.rows[row_ref.row_idx].boxes.clone(),
+        })
+    }
+
+    // outlines: floated boxes blue, min-width boxes green, wrappers grey
+    pub fn debug_styles(&self, drawer: &TableDrawer) -> String {
+        let mut floats = Vec::new();
+        let mut softs = Vec::new();
+        let mut wrappers = Vec::new();
+        for rr in &drawer.row_set {
+            if let Ok(geom) = self.geometry(*rr) {
+                for b in geom.boxes {
+                    let cls = format!(".{}",b.class);
+                    let list = match (b.arg,b.float) {
+                        (None,_) => &mut wrappers,
+                        (Some(_),Some(_)) => &mut floats,
+                        (Some(_),None) => &mut softs,
+                    };
+                    if !list.contains(&cls) { list.push(cls); }
+                }
+            }
+        }
+        let mut res = String::new();
+        for (list,color) in [(wrappers,"rgba(128,128,128,0.8)"),(floats,"rgba(0,0,255,0.6)"),(softs,"rgba(0,160,0,0.6)")] {
+            if !list.is_empty() {
+                res += &format!("{} {{ outline: 1px dashed {}; outline-offset: -1px; }}\n",list.join(", "),color);
+            }
+        }
+        res
+    }
+
+    pub fn scripts(&self, drawer: &TableDrawer) -> String {
+        let mut res = String::new();
+        if drawer.row_actions {
+            res += ROW_ACTIONS_JS;
+        }
+        if drawer.selection {
+            res += SELECTION_JS;
+        }
+        if drawer.editing {
+            res += EDIT_JS;
+        }
         res
     }
+
+    // the same styles and scripts, one dependency per row and per client feature
+    pub fn dependencies(&self, drawer: &TableDrawer) -> Vec<Dependency> {
+        let mut res = Vec::new();
+        for rr in &drawer.row_set {
+            if let Some(row) = self.tables.get(rr.table_idx).and_then(|t| t.rows.get(rr.row_idx).map(|r| (t,r))) {
+                let mut single = TableDrawer::new();
+                single.register(*rr);
+                res.push(Dependency::new(format!("table:{}_r{}",row.0.prefix,rr.row_idx)).css(&row.1.styles).debug_css(&self.debug_styles(&single)));
+            }
+        }
+        if drawer.row_actions {
+            res.push(Dependency::new("table:row_actions").css(ROW_ACTIONS_CSS).js(ROW_ACTIONS_JS));
+        }
+        if drawer.selection {
+            res.push(Dependency::new("table:selection").css(SELECTION_CSS).js(SELECTION_JS));
+        }
+        if drawer.editing {
+            res.push(Dependency::new("table:editing").css(EDIT_CSS).js(EDIT_JS));
+        }
+        res
+    }
+
+    pub fn view(&self) -> TableView<'_> {
+        TableView {
+            builder: self,
+            drawer: TableDrawer::new(),
+            el: Element::new("div"),
+        }
+    }
+}
+
+// rows of one page part with their own drawer, so a page pulls in exactly the styles they use
+#[derive(Debug)]
+pub struct TableView<'a> {
+    builder: &'a TableBuilder,
+    drawer: TableDrawer,
+    el: Element,
+}
+impl TableView<'_> {
+    pub fn row<S: AsRef<str>>(&mut self, row_ref: RowRef, class: &str, options: &RowOptions, values: &[S]) -> Result<(),TableError> {
+        let mut s = String::new();
+        self.builder.write_row(&mut s,row_ref,class,options,values,&mut self.drawer)?;
+        s += "\n";
+        self.el.push(Node::Raw(s));
+        Ok(())
+    }
+    pub fn bulk_actions(&mut self, table: TableRef) -> Result<(),TableError> {
+        let s = self.builder.bulk_actions(table,&mut self.drawer)?;
+        self.el.push(Node::Raw(s));
+        Ok(())
+    }
+    pub fn editable(&mut self, table: TableRef, cell: &EditableCell) -> Result<String,TableError> {
+        self.builder.editable(table,cell,&mut self.drawer)
+    }
+    pub fn push<N: Into<Node>>(&mut self, n: N) {
+        self.el.push(n);
+    }
+}
+impl Component for TableView<'_> {
+    fn render(&self) -> Element {
+        self.el.clone()
+    }
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.builder.dependencies(&self.drawer)
+    }
 }
 
 
@@ -192,32 +1026,59 @@ impl DivFixed {
             DivFixed::Vec(v) => v.len(),
         }
     }
-    fn divs(&self, styles: &mut String, pads: &mut Vec<String>, divs: &mut Vec<String>, args: &mut Vec<usize>, arg_offset: usize, arg_length: usize, cls_prefix: String, float: &str) {
+    fn divs(&self, parts: &mut RowParts, divs: &mut Vec<String>, arg_offset: usize, cls_prefix: String, float: Side) {
         match self {
             DivFixed::One(RealFixed { padding_left, padding_right, width }) => {              
-                *styles += &format!(".{} {{ padding-left: {}px; padding-right: {}px; width: {}px; float: {}; overflow: hidden; }}\n",cls_prefix,padding_left, padding_right, width, float);
+                parts.styles += &format!(".{} {{ padding-left: {}px; padding-right: {}px; width: {}px; float: {}; overflow: hidden; }}\n",cls_prefix,padding_left, padding_right, width, float.as_str());
                 divs.push(format!("<div class='{}'>",cls_prefix));
                 divs.push("</div>\n".to_string());
-                pads.push(cls_prefix);
+                parts.cell(BoxGeometry {
+                    class: cls_prefix,
+                    depth: parts.depth,
+                    arg: Some(arg_offset),
+                    padding_left: *padding_left,
+                    padding_right: *padding_right,
+                    width: BoxWidth::Fixed(*width),
+                    margin: None,
+                    float: Some(float),
+                });
             },
             DivFixed::Vec(vf) => {
+                parts.styles += &format!(".{} {{ width: {}px; float: {}; overflow: hidden; }}\n",cls_prefix,vf.iter().fold(0,|acc,x| acc + x.size()),float.as_str());
+                parts.wrapper(BoxGeometry {
+                    class: cls_prefix.clone(),
+                    depth: parts.depth,
+                    arg: None,
+                    padding_left: 0,
+                    padding_right: 0,
+                    width: BoxWidth::Fixed(vf.iter().fold(0,|acc,x| acc + x.size())),
+                    margin: None,
+                    float: Some(float),
+                });
+                parts.depth += 1;
                 let mut v = Vec::new();
                 for (i,r) in vf.iter().enumerate() {
                     let RealFixed { padding_left, padding_right, width } = r;
                     let cls = format!("{}_c{}",cls_prefix,i);                    
-                    *styles += &format!(".{} {{ padding-left: {}px; padding-right: {}px; width: {}px; overflow: hidden; float: left; }}\n",cls,padding_left, padding_right, width);
-                    divs.push(format!("<div class='{}'>",cls));
-                    divs.push("</div>\n".to_string());
-                    pads.push(cls);
+                    parts.styles += &format!(".{} {{ padding-left: {}px; padding-right: {}px; width: {}px; overflow: hidden; float: left; }}\n",cls,padding_left, padding_right, width);
+                    v.push(format!("<div class='{}'>",cls));
+                    v.push("</div>\n".to_string());
+                    parts.cell(BoxGeometry {
+                        class: cls,
+                        depth: parts.depth,
+                        arg: Some(arg_offset + i),
+                        padding_left: *padding_left,
+                        padding_right: *padding_right,
+                        width: BoxWidth::Fixed(*width),
+                        margin: None,
+                        float: Some(Side::Left),
+                    });
                 }
-                *styles += &format!(".{} {{ width: {}px; float: {}; overflow: hidden; }}\n",cls_prefix,vf.iter().fold(0,|acc,x| acc + x.size()),float);
+                parts.depth -= 1;
                 add_div(&cls_prefix,&mut v);
                 divs.extend(v);
             },
         }
-        for arg in 0 .. arg_length {
-            args.push(arg_offset + arg);
-        }
     }
 }
 #[derive(Debug)]
@@ -291,14 +1152,20 @@ struct DivSoftReal {
     margin: Option<usize>,
 }
 impl DivSoftReal {
-    fn divs(&self, styles: &mut String, pads: &mut Vec<String>, divs: &mut Vec<String>, args: &mut Vec<usize>, arg_offset: usize, arg_length: usize, cls_prefix: String, float: &str) {  
-        *styles += &format!(".{} {{ padding-left: {}px; padding-right: {}px; min-width: {}px; margin-{}: {}px; overflow: hidden; }}\n",cls_prefix,self.padding_left, self.padding_right, self.min_width, float, match self.margin { Some(m) => m, None => 0 });
+    fn divs(&self, parts: &mut RowParts, divs: &mut Vec<String>, arg_offset: usize, cls_prefix: String, float: Side) {
+        parts.styles += &format!(".{} {{ padding-left: {}px; padding-right: {}px; min-width: {}px; margin-{}: {}px; overflow: hidden; }}\n",cls_prefix,self.padding_left, self.padding_right, self.min_width, float.as_str(), self.margin.unwrap_or_default());
         divs.push(format!("<div class='{}'>",cls_prefix));
         divs.push("</div>\n".to_string());
-        pads.push(cls_prefix);
-        for arg in 0 .. arg_length {
-            args.push(arg_offset + arg);
-        }
+        parts.cell(BoxGeometry {
+            class: cls_prefix,
+            depth: parts.depth,
+            arg: Some(arg_offset),
+            padding_left: self.padding_left,
+            padding_right: self.padding_right,
+            width: BoxWidth::Min(self.min_width),
+            margin: Some((float,self.margin.unwrap_or_default())),
+            float: None,
+        });
     }
 }
 #[derive(Debug)]
@@ -318,42 +1185,60 @@ impl DivSoft {
             DivSoft::Real(..) => 1,
         }
     }
-    fn divs(&self, styles: &mut String, pads: &mut Vec<String>, divs: &mut Vec<String>, args: &mut Vec<usize>, arg_offset: usize, arg_length: usize, cls_prefix: String, float: &str) {
+    fn divs(&self, parts: &mut RowParts, divs: &mut Vec<String>, arg_offset: usize, cls_prefix: String, float: Side) {
         match &self {
             DivSoft::Phantom { fixed, soft, min_width, margin } => match fixed {
-                Fixed::None => soft.divs(styles,pads,divs,args,arg_offset,arg_length,cls_prefix,float),
-                Fixed::Left(left) => {
-                    let l_cnt = left.count();
-                    let r_cnt = 1; // soft.count();                    
-                    let cls = format!("{}_l",cls_prefix);
-                    let mut tmp_l = Vec::new();
-                    left.divs(styles,pads,&mut tmp_l,args,arg_offset,l_cnt,cls,"left");                    
-                    let cls = format!("{}_r",cls_prefix);
-                    let mut tmp_r = Vec::new();
-                    soft.divs(styles,pads,&mut tmp_r,args,arg_offset+l_cnt,r_cnt,cls,"left");
-                    concat_divs(&mut tmp_l,tmp_r);
-                    *styles += &format!(".{} {{ min-width: {}px; margin-{}: {}px; overflow: hidden; }}\n",cls_prefix,min_width, float, match margin { Some(m) => *m, None => 0 });
-                    add_div("row",&mut tmp_l);
-                    add_div(&cls_prefix,&mut tmp_l);
-                    divs.extend(tmp_l);
-                },
-                Fixed::Right(right) => {
-                    let r_cnt = right.count();
-                    let l_cnt = 1; //soft.count();                    
-                    let cls = format!("{}_r",cls_prefix);
-                    let mut tmp_r = Vec::new();
-                    right.divs(styles,pads,&mut tmp_r,args,arg_offset+l_cnt,r_cnt,cls,"right");
-                    let cls = format!("{}_l",cls_prefix);
-                    let mut tmp_l = Vec::new();
-                    soft.divs(styles,pads,&mut tmp_l,args,arg_offset,l_cnt,cls,"right");
-                    concat_divs(&mut tmp_r,tmp_l);
-                    *styles += &format!(".{} {{ min-width: {}px; margin-{}: {}px; overflow: hidden; }}\n",cls_prefix,min_width, float, match margin { Some(m) => *m, None => 0 });
-                    add_div("row",&mut tmp_r);
-                    add_div(&cls_prefix,&mut tmp_r);
-                    divs.extend(tmp_r);
+                Fixed::None => soft.divs(parts,divs,arg_offset,cls_prefix,float),
+                Fixed::Left(left) | Fixed::Right(left) => {
+                    parts.styles += &format!(".{} {{ min-width: {}px; margin-{}: {}px; overflow: hidden; }}\n",cls_prefix,min_width, float.as_str(), margin.unwrap_or_default());
+                    parts.wrapper(BoxGeometry {
+                        class: cls_prefix.clone(),
+                        depth: parts.depth,
+                        arg: None,
+                        padding_left: 0,
+                        padding_right: 0,
+                        width: BoxWidth::Min(*min_width),
+                        margin: Some((float,margin.unwrap_or_default())),
+                        float: None,
+                    });
+                    parts.wrapper(BoxGeometry {
+                        class: "row".to_string(),
+                        depth: parts.depth + 1,
+                        arg: None,
+                        padding_left: 0,
+                        padding_right: 0,
+                        width: BoxWidth::Auto,
+                        margin: None,
+                        float: None,
+                    });
+                    parts.depth += 2;
+                    let mut tmp = match fixed {
+                        Fixed::Left(_) => {
+                            let l_cnt = left.count();
+                            let mut tmp_l = Vec::new();
+                            left.divs(parts,&mut tmp_l,arg_offset,format!("{}_l",cls_prefix),Side::Left);
+                            let mut tmp_r = Vec::new();
+                            soft.divs(parts,&mut tmp_r,arg_offset+l_cnt,format!("{}_r",cls_prefix),Side::Left);
+                            concat_divs(&mut tmp_l,tmp_r);
+                            tmp_l
+                        },
+                        _ => {
+                            let l_cnt = 1; //soft.count();
+                            let mut tmp_r = Vec::new();
+                            left.divs(parts,&mut tmp_r,arg_offset+l_cnt,format!("{}_r",cls_prefix),Side::Right);
+                            let mut tmp_l = Vec::new();
+                            soft.divs(parts,&mut tmp_l,arg_offset,format!("{}_l",cls_prefix),Side::Right);
+                            concat_divs(&mut tmp_r,tmp_l);
+                            tmp_r
+                        },
+                    };
+                    parts.depth -= 2;
+                    add_div("row",&mut tmp);
+                    add_div(&cls_prefix,&mut tmp);
+                    divs.extend(tmp);
                 },
             },
-            DivSoft::Real(r) => r.divs(styles,pads,divs,args,arg_offset,arg_length,cls_prefix,float),
+            DivSoft::Real(r) => r.divs(parts,divs,arg_offset,cls_prefix,float),
         }
     }
 }
@@ -366,9 +1251,6 @@ struct Phantom {
     soft: DivSoft,
 }
 impl Phantom {
-    fn count(&self) -> usize {
-        self.fixed.count() + self.soft.count()
-    }
     fn from_column(c: SoftColumn, min_width: usize, _percentage: usize, most_left: bool, most_right: bool, table: &mut TableConf) -> Result<Phantom,TableError> {
         let mut left = Vec::new();
         let mut right = Vec::new();
@@ -381,8 +1263,8 @@ impl Phantom {
             }
         }
         if unk != 1 { return Err(TableError::MustBeOneSoftColumn(table.uid.clone())); }
-        if left.len() == 0 {
-            if right.len() == 0 {
+        if left.is_empty() {
+            if right.is_empty() {
                 Ok(Phantom{
                     //percentage, min_width,
                     fixed: Fixed::None, soft: DivSoft::Real(DivSoftReal {
@@ -410,7 +1292,7 @@ impl Phantom {
                 })
             }
         } else {
-            if right.len() == 0 {
+            if right.is_empty() {
                 let fixed = Fixed::left(left,most_left,table);
                 let margin_left = fixed.size();
                 if min_width <= margin_left {
@@ -452,31 +1334,25 @@ impl Phantom {
             }
         }
     }
-    fn divs(&self, styles: &mut String, pads: &mut Vec<String>, divs: &mut Vec<String>, args: &mut Vec<usize>, arg_offset: usize, arg_length: usize, cls_prefix: String) {
+    fn divs(&self, parts: &mut RowParts, divs: &mut Vec<String>, cls_prefix: String) {
         // self.fixed self.soft
         match &self.fixed {
-            Fixed::None => self.soft.divs(styles,pads,divs,args,arg_offset,arg_length,cls_prefix,"left"),
+            Fixed::None => self.soft.divs(parts,divs,0,cls_prefix,Side::Left),
             Fixed::Left(left) => {
                 let l_cnt = left.count();
-                let r_cnt = self.soft.count();
-                let cls = format!("{}_l",cls_prefix);
                 let mut tmp_l = Vec::new();
-                left.divs(styles,pads,&mut tmp_l,args,arg_offset,l_cnt,cls,"left");                
-                let cls = format!("{}_r",cls_prefix);
+                left.divs(parts,&mut tmp_l,0,format!("{}_l",cls_prefix),Side::Left);
                 let mut tmp_r = Vec::new();
-                self.soft.divs(styles,pads,&mut tmp_r,args,arg_offset+l_cnt,r_cnt,cls,"left");
+                self.soft.divs(parts,&mut tmp_r,l_cnt,format!("{}_r",cls_prefix),Side::Left);
                 concat_divs(&mut tmp_l,tmp_r);
                 divs.extend(tmp_l);
             },
             Fixed::Right(right) => {
-                let r_cnt = right.count();
                 let l_cnt = self.soft.count();                    
-                let cls = format!("{}_r",cls_prefix);
                 let mut tmp_r = Vec::new();
-                right.divs(styles,pads,&mut tmp_r,args,arg_offset+l_cnt,r_cnt,cls,"right");
-                let cls = format!("{}_l",cls_prefix);
+                right.divs(parts,&mut tmp_r,l_cnt,format!("{}_r",cls_prefix),Side::Right);
                 let mut tmp_l = Vec::new();
-                self.soft.divs(styles,pads,&mut tmp_l,args,arg_offset,l_cnt,cls,"right");
+                self.soft.divs(parts,&mut tmp_l,0,format!("{}_l",cls_prefix),Side::Right);
                 concat_divs(&mut tmp_r,tmp_l);
                 divs.extend(tmp_r);
             },
@@ -489,7 +1365,7 @@ enum Div {
     //Vec(Vec<Phantom>),
 }
 
-fn add_div(cls: &str, divs: &mut Vec<String>) {
+fn add_div(cls: &str, divs: &mut [String]) {
     let ln = divs.len();
     if ln > 0 {
         divs[0] = format!("<div class='{}'>\n{}",cls,divs[0]);
@@ -501,7 +1377,7 @@ fn concat_divs(l: &mut Vec<String>, mut r: Vec<String>) {
     match l.pop() {
         None => l.extend(r),
         Some(last_l) => {
-            match r.len() > 0 { 
+            match !r.is_empty() {
                 true => r[0] = format!("{}{}",last_l,r[0]),
                 false => r.push(last_l),
             }
@@ -510,7 +1386,7 @@ fn concat_divs(l: &mut Vec<String>, mut r: Vec<String>) {
     }
 }
 
-fn create_soft(table: &mut TableConf, min_width: usize, mut columns: Vec<SoftColumn>) -> Result<RowRef,TableError> {
+fn create_soft(table: &mut TableConf, min_width: usize, mut columns: Vec<SoftColumn>, overflow: Vec<Overflow>) -> Result<RowRef,TableError> {
     let div = match columns.len() {
         0 | 1 => match columns.pop() {
             None => return Err(TableError::EmptySoft(table.uid.clone())),
@@ -526,16 +1402,12 @@ fn create_soft(table: &mut TableConf, min_width: usize, mut columns: Vec<SoftCol
         },
     };
     let row_idx = table.rows.len();
-    let mut styles = String::new();
+    let mut parts = RowParts::default();
     let mut divs = Vec::new();
-    let mut args = Vec::new();
-    let mut pads = Vec::new();
     match div {
-        Div::One(ph) => {
-            let cnt = ph.count();
-            ph.divs(&mut styles,&mut pads,&mut divs,&mut args,0,cnt,format!("{}",table.uid))
-        },
+        Div::One(ph) => ph.divs(&mut parts,&mut divs,format!("{}_r{}",table.prefix,row_idx)),
     }
+    let RowParts { mut styles, pads, args, boxes, .. } = parts;
     let mut first = true;
     for cls in &pads {
         if !first { styles += ", "; }
@@ -544,12 +1416,15 @@ fn create_soft(table: &mut TableConf, min_width: usize, mut columns: Vec<SoftCol
         first = false;
     }
     styles += &format!("{{ padding-top: {}px; padding-bottom: {}px; }}\n", table.half_padding * 2, table.half_padding * 2);
+    let cells = pads.into_iter().zip(args.iter().copied()).collect::<Vec<_>>();
+    styles += &overflow_styles(&cells,&overflow);
 
-    table.rows.push(Row{ styles, divs, args: Some(args) });
+    let template = compile(divs,Some(&args),&overflow);
+    table.rows.push(Row{ styles, template, boxes });
     Ok(RowRef{ table_idx: table.index, row_idx })
 }
 
-fn create_fixed(table: &mut TableConf, width: usize, columns: &[Option<usize>]) -> Result<RowRef,TableError> {
+fn create_fixed(table: &mut TableConf, width: usize, columns: &[Option<usize>], overflow: Vec<Overflow>) -> Result<RowRef,TableError> {
     let cnt = columns.len();
     let pads = (cnt + 1) * 2 * table.half_padding;
     let mut unk = 0;
@@ -569,6 +1444,7 @@ fn create_fixed(table: &mut TableConf, width: usize, columns: &[Option<usize>])
         let mut ctrl = 0;
         let mut u_idx = 0;
         let mut col_classes = Vec::new();
+        let mut widths = Vec::new();
         let mut st = String::new();
         for (col_idx, c) in columns.iter().enumerate() {
             let w = match c {
@@ -580,9 +1456,14 @@ fn create_fixed(table: &mut TableConf, width: usize, columns: &[Option<usize>])
                 }
             };
             ctrl += w;
-            let col_class = format!("{}_r{}_c{}",table.uid,row_idx,col_idx);
-            st += &format!(".{} {{ width: {}px; }}\n",col_class,w);
+            let col_class = format!("{}_r{}_c{}",table.prefix,row_idx,col_idx);
+            // clipped cells hide what does not fit, as in soft rows; ellipsis brings its own
+            match overflow.get(col_idx).copied().unwrap_or_default() {
+                Overflow::Clip => st += &format!(".{} {{ width: {}px; overflow: hidden; }}\n",col_class,w),
+                _ => st += &format!(".{} {{ width: {}px; }}\n",col_class,w),
+            }
             col_classes.push(col_class);
+            widths.push(w);
         }
         if (ctrl + pads) != width {
             return Err(TableError::FixedRowTooLong {
@@ -619,13 +1500,28 @@ fn create_fixed(table: &mut TableConf, width: usize, columns: &[Option<usize>])
         }
         styles += &format!("{{ padding-left: {}px; }}\n", table.half_padding);
         styles += &st;
+        let cells = col_classes.iter().cloned().zip(0 ..).collect::<Vec<_>>();
+        styles += &overflow_styles(&cells,&overflow);
         
+        let hp = table.half_padding;
+        let last = col_classes.len() - 1;
+        let boxes = col_classes.iter().zip(widths).enumerate().map(|(i,(cls,w))| BoxGeometry {
+            class: cls.clone(),
+            depth: 0,
+            arg: Some(i),
+            padding_left: match i == 0 { true => 2, false => 1 } * hp,
+            padding_right: match i == last { true => 2, false => 1 } * hp,
+            width: BoxWidth::Fixed(w),
+            margin: None,
+            float: Some(Side::Left),
+        }).collect();
         let mut divs = col_classes.into_iter().enumerate().map(|(i,cls)| match i {
             0 => format!("<div class='{}'>",cls),
             _ => format!("</div>\n<div class='{}'>",cls),
         }).collect::<Vec<_>>();
         divs.push("</div>\n".to_string());
-        table.rows.push(Row{ styles, divs, args: None });
+        let template = compile(divs,None,&overflow);
+        table.rows.push(Row{ styles, template, boxes });
         Ok(RowRef{ table_idx: table.index, row_idx })
     } else {
         Err(TableError::FixedRowTooLong {
@@ -638,23 +1534,39 @@ fn create_fixed(table: &mut TableConf, width: usize, columns: &[Option<usize>])
 mod tests {
     use super::*;
 
+    use crate::layout::{evaluate,LayoutError};
+
+    fn offsets(styles: &str, body: &str, width: usize, classes: &[&str]) -> Vec<(usize,usize)> {
+        let layout = evaluate(styles,body,width).unwrap();
+        classes.iter().map(|c| {
+            let b = layout.get(c).unwrap();
+            (b.x,b.width)
+        }).collect()
+    }
+
     #[test]
     fn fixed_1() {
         let mut tb = TableBuilder::new();
-        let table = tb.table_fixed("locs",250);
+        let table = tb.table_fixed("locs",250).unwrap();
         let row = tb.create_row_fixed(table,&[None,Some(44),Some(44)]).unwrap();
         let mut drawer = TableDrawer::new();
         let body = tb.row(row,"locs_r0",vec!["Row1".to_owned(),"1".to_owned(),"10".to_owned()],&mut drawer).unwrap();
         let styles = tb.styles(&drawer);
-        println!("{}",styles);
-        println!("{}",body);
-        panic!("");
+        assert_eq!(offsets(&styles,&body,250,&["locs_r0","locs_r0_c0","locs_r0_c1","locs_r0_c2"]),vec![(0,250),(0,152),(152,48),(200,50)]);
+        assert!(matches!(evaluate(&styles,&body,260),Err(LayoutError::Gap{ covered: 250, .. })));
+        assert!(matches!(evaluate(&styles,&body,240),Err(LayoutError::Gap{ line: 0, covered: 200, .. })));
+        let optimized = tb.optimized_styles(&drawer,false);
+        assert_eq!(optimized,".locs_r0_c0 { padding: 4px 2px 4px 4px; float: left; width: 146px; overflow: hidden; }
+.locs_r0_c1 { padding: 4px 2px; float: left; width: 44px; overflow: hidden; }
+.locs_r0_c2 { padding: 4px 4px 4px 2px; float: left; width: 44px; overflow: hidden; }
+");
+        assert_eq!(evaluate(&optimized,&body,250),evaluate(&styles,&body,250));
     }
 
     #[test]
     fn soft_1() {
         let mut tb = TableBuilder::new();
-        let table = tb.table_soft("pros",742);
+        let table = tb.table_soft("pros",742).unwrap();
         let row = tb.create_row_soft(table,vec![
             SoftColumn {
                 percentage: None,
@@ -662,17 +1574,18 @@ mod tests {
             },
         ]).unwrap();
         let mut drawer = TableDrawer::new();
-        let body = tb.row(row,"pros_r0",vec!["Slot".to_owned(),"Data".to_owned(),"10".to_owned()],&mut drawer).unwrap();
+        let body = tb.row(row,"pros_row",vec!["Slot".to_owned(),"Data".to_owned(),"10".to_owned()],&mut drawer).unwrap();
         let styles = tb.styles(&drawer);
-        println!("{}",styles);
-        println!("{}",body);
-        panic!("");
+        let cells = ["pros_r0_l","pros_r0_r","row","pros_r0_r_r","pros_r0_r_l"];
+        assert_eq!(offsets(&styles,&body,742,&cells),vec![(0,156),(156,586),(156,586),(696,46),(156,540)]);
+        assert_eq!(offsets(&styles,&body,1000,&cells),vec![(0,156),(156,844),(156,844),(954,46),(156,798)]);
+        assert!(matches!(evaluate(&styles,&body,741),Err(LayoutError::MinWidth{ .. })));
     }
 
-     #[test]
+    #[test]
     fn soft_2() {
         let mut tb = TableBuilder::new();
-        let table = tb.table_soft("pros",742);
+        let table = tb.table_soft("pros",742).unwrap();
         let row = tb.create_row_soft(table,vec![
             SoftColumn {
                 percentage: None,
@@ -682,12 +1595,217 @@ mod tests {
         let mut drawer = TableDrawer::new();
         let body = tb.row(row,"pros_rh",vec!["Header".to_owned()],&mut drawer).unwrap();
         let styles = tb.styles(&drawer);
-        println!("{}",styles);
-        println!("{}",body);
-        panic!("");
+        assert_eq!(offsets(&styles,&body,742,&["pros_r0"]),vec![(0,742)]);
+        assert_eq!(evaluate(&styles,&body,900).unwrap().get("pros_r0").unwrap().content_width,892);
+        assert!(matches!(evaluate(&styles,&body,700),Err(LayoutError::MinWidth{ min_width: 734, .. })));
     }
 
-    
-}
+    #[test]
+    fn soft_layouts() {
+        let specs: Vec<Vec<Option<usize>>> = vec![
+            vec![Some(30),Some(150),None],
+            vec![None,Some(40),Some(30),Some(20)],
+            vec![Some(30),Some(20),None,Some(40),Some(50)],
+            vec![Some(10),None,Some(10)],
+        ];
+        for (i,subcolumns) in specs.into_iter().enumerate() {
+            let mut tb = TableBuilder::new();
+            let table = tb.table_soft("t",500).unwrap();
+            let cnt = subcolumns.len();
+            let row = tb.create_row_soft(table,vec![SoftColumn { percentage: None, subcolumns }]).unwrap();
+            let mut drawer = TableDrawer::new();
+            let body = tb.row(row,"t_row",(0 .. cnt).map(|i| i.to_string()).collect(),&mut drawer).unwrap();
+            let styles = tb.styles(&drawer);
+            for width in [500,640,1280] {
+                let layout = match evaluate(&styles,&body,width) {
+                    Ok(l) => l,
+                    Err(e) => panic!("spec {} at {}px: {:?}\n{}\n{}",i,width,e,styles,body),
+                };
+                assert_eq!(evaluate(&tb.optimized_styles(&drawer,true),&body,width).as_ref(),Ok(&layout));
+                let cells = tb.geometry(row).unwrap().cells().map(|c| c.class.clone()).collect::<Vec<_>>();
+                assert!(cells.iter().all(|c| layout.get(c).is_some()));
+            }
+            assert!(evaluate(&styles,&body,499).is_err());
+        }
+    }
+
+    #[test]
+    fn row_options() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("reqs",250).unwrap();
+        let row = tb.create_row_fixed(table,&[None,Some(44)]).unwrap();
+        let mut drawer = TableDrawer::new();
+        let plain = tb.row(row,"reqs_r0",vec!["Plain".to_owned(),"1".to_owned()],&mut drawer).unwrap();
+        assert!(plain.starts_with("<div class='reqs_r0'>\n"));
+        assert_eq!(tb.scripts(&drawer),"");
+
+        let opts = RowOptions::new()
+            .id("req-17")
+            .href("/requests/17?full=1&x='y'")
+            .data("Status","failed")
+            .data("'<>","x")
+            .detail(Block::new("req_detail").text("details"));
+        let body = tb.row_with(row,"reqs_r0",&opts,vec!["Row".to_owned(),"2".to_owned()],&mut drawer).unwrap();
+        assert!(body.starts_with("<div class='reqs_r0 row_link' id='req-17' data-status='failed' data-row-href='/requests/17?full=1&amp;x=&#39;y&#39;' data-row-detail='1'>\n"));
+        assert!(body.ends_with("</div>\n<div class='row_detail' hidden>\n<div class='req_detail'>details</div>\n</div>\n"));
+
+        let lazy = tb.row_with(row,"reqs_r0",&RowOptions::new().detail_url("/requests/18/detail"),vec![],&mut drawer).unwrap();
+        assert!(lazy.ends_with("<div class='row_detail' data-detail-url='/requests/18/detail' hidden></div>\n"));
+        assert!(tb.styles(&drawer).contains(".row_detail"));
+        assert!(tb.scripts(&drawer).contains("data-row-href"));
+    }
+
+    #[test]
+    fn selection() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_soft("jobs",600).unwrap();
+        tb.with_selection(table,Selection::new("jobs_bulk","/jobs/bulk").action("cancel","Cancel").action("retry","Retry")).unwrap();
+        let row = tb.create_row_soft(table,vec![
+            SoftColumn {
+                percentage: None,
+                subcolumns: vec![None,Some(80)],
+            },
+        ]).unwrap();
+        assert!(matches!(tb.with_selection(table,Selection::new("x","/x")),Err(TableError::SelectionAfterRows(_))));
+        let mut drawer = TableDrawer::new();
+        let head = tb.row_with(row,"jobs_h",&RowOptions::new().select_all(),vec!["Job".to_owned(),"State".to_owned()],&mut drawer).unwrap();
+        assert!(head.contains("<input type='checkbox' data-select-all='jobs_bulk'></div>"));
+        let body = tb.row_with(row,"jobs_r",&RowOptions::new().key(17),vec!["build".to_owned(),"failed".to_owned()],&mut drawer).unwrap();
+        assert!(body.contains("<input type='checkbox' form='jobs_bulk' name='keys' value='17' data-select='jobs_bulk'></div>"));
+        assert!(body.contains(">build</div>") && body.contains(">failed</div>"));
+        let form = tb.bulk_actions(table,&mut drawer).unwrap();
+        assert!(form.starts_with("<form id='jobs_bulk' class='bulk_actions' method='post' action='/jobs/bulk'>"));
+        assert!(form.contains("<button type='submit' name='action' value='retry'>Retry</button>"));
+        assert!(tb.scripts(&drawer).contains("data-select-all"));
+    }
 
+    #[test]
+    fn editable() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("flags",400).unwrap();
+        let row = tb.create_row_fixed(table,&[None,Some(100)]).unwrap();
+        let mut drawer = TableDrawer::new();
+        let cell = EditableCell::select("f<1>","state","on",vec![("on".to_owned(),"Enabled".to_owned()),("off".to_owned(),"Disabled & hidden".to_owned())]);
+        assert!(matches!(tb.editable(table,&cell,&mut drawer),Err(TableError::NoEditEndpoint(_))));
+        tb.with_edit_endpoint(table,"/flags/edit").unwrap();
+        let html = tb.editable(table,&cell,&mut drawer).unwrap();
+        assert_eq!(html,"<span class='cell_edit' data-edit-url='/flags/edit' data-edit-key='f&lt;1&gt;' data-edit-field='state' data-edit-kind='select' data-edit-value='on' data-edit-options='on=Enabled&amp;off=Disabled+%26+hidden'>Enabled</span>");
+        let quota = tb.editable(table,&EditableCell::number(7,"quota",1500),&mut drawer).unwrap();
+        tb.row(row,"flags_r",vec![html,quota],&mut drawer).unwrap();
+        assert!(tb.scripts(&drawer).contains("cellEditSave"));
+        assert!(tb.styles(&drawer).contains(".cell_error"));
+    }
 
+    #[test]
+    fn overflow() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("paths",300).unwrap();
+        let row = tb.create_row_fixed_with(table,&[None,Some(60),Some(60)],&[Overflow::Ellipsis,Overflow::BreakAll]).unwrap();
+        let mut drawer = TableDrawer::new();
+        let body = tb.row(row,"paths_r",vec!["<b>/very/long/path</b> 'x'".to_owned(),"0xdeadbeef".to_owned(),"ok".to_owned()],&mut drawer).unwrap();
+        assert!(body.contains("<div class='paths_r0_c0' title='/very/long/path &#39;x&#39;'><b>/very/long/path</b> 'x'</div>"));
+        assert!(body.contains("<div class='paths_r0_c1'>0xdeadbeef</div>"));
+        let styles = tb.styles(&drawer);
+        assert!(styles.contains(".paths_r0_c0 { overflow: hidden; white-space: nowrap; text-overflow: ellipsis; }\n"));
+        assert!(styles.contains(".paths_r0_c1 { white-space: normal; word-break: break-all; }\n"));
+        assert!(styles.contains(".paths_r0_c0 { width: 164px; }\n"));
+        assert!(styles.contains(".paths_r0_c2 { width: 60px; overflow: hidden; }\n"));
+        assert!(!styles.contains(".paths_r0_c2 {  "));
+
+        let table = tb.table_soft("msgs",600).unwrap();
+        let row = tb.create_row_soft_with(table,vec![
+            SoftColumn {
+                percentage: None,
+                subcolumns: vec![Some(100),None],
+            },
+        ],&[Overflow::Clip,Overflow::Wrap]).unwrap();
+        let mut drawer = TableDrawer::new();
+        tb.row(row,"msgs_r",vec!["a".to_owned(),"b".to_owned()],&mut drawer).unwrap();
+        assert!(tb.styles(&drawer).contains(".msgs_r0_r { white-space: normal; overflow-wrap: break-word; }\n"));
+    }
+
+    #[test]
+    fn geometry() {
+        let mut tb = TableBuilder::new();
+        let table = tb.table_soft("pros",742).unwrap();
+        let row = tb.create_row_soft(table,vec![
+            SoftColumn {
+                percentage: None,
+                subcolumns: vec![Some(150),None,Some(40),Some(30)],
+            },
+        ]).unwrap();
+        let geom = tb.geometry(row).unwrap();
+        let boxes = geom.boxes.iter().map(|b| (b.class.as_str(),b.depth,b.arg,b.width,b.margin,b.float)).collect::<Vec<_>>();
+        assert_eq!(boxes,vec![
+            ("pros_r0_l",0,Some(0),BoxWidth::Fixed(150),None,Some(Side::Left)),
+            ("pros_r0_r",0,None,BoxWidth::Min(586),Some((Side::Left,156)),None),
+            ("row",1,None,BoxWidth::Auto,None,None),
+            ("pros_r0_r_r",2,None,BoxWidth::Fixed(80),None,Some(Side::Right)),
+            ("pros_r0_r_r_c0",3,Some(2),BoxWidth::Fixed(40),None,Some(Side::Left)),
+            ("pros_r0_r_r_c1",3,Some(3),BoxWidth::Fixed(30),None,Some(Side::Left)),
+            ("pros_r0_r_l",2,Some(1),BoxWidth::Min(502),Some((Side::Right,80)),None),
+        ]);
+        assert_eq!(geom.cells().count(),4);
+
+        let mut drawer = TableDrawer::new();
+        let body = tb.row(row,"pros_row",vec!["Slot".to_owned(),"Data".to_owned(),"10".to_owned(),"11".to_owned()],&mut drawer).unwrap();
+        for b in &geom.boxes {
+            assert!(body.contains(&format!("<div class='{}'>",b.class)),"{} is not rendered",b.class);
+        }
+        assert!(tb.debug_styles(&drawer).contains(".pros_r0_r_r_c0, .pros_r0_r_r_c1 { outline: 1px dashed rgba(0,0,255,0.6);"));
+    }
+
+    #[test]
+    fn uids() {
+        let mut tb = TableBuilder::new();
+        tb.table_fixed("jobs",300).unwrap();
+        assert!(matches!(tb.table_soft("jobs",300),Err(TableError::DuplicateUid(_))));
+        assert!(matches!(tb.table_soft("jobs_r1",300),Err(TableError::UidCollision(..))));
+        assert!(matches!(tb.table_soft("job list",300),Err(TableError::InvalidUid(_))));
+        assert!(matches!(tb.table_soft("7jobs",300),Err(TableError::InvalidUid(_))));
+        assert!(matches!(tb.table_soft("",300),Err(TableError::InvalidUid(_))));
+        tb.table_soft("jobs_archive",300).unwrap();
+
+        let mut other = TableBuilder::new();
+        other.table_fixed("jobs",300).unwrap();
+        assert!(matches!(tb.check_compatible(&other),Err(TableError::UidCollision(..))));
+
+        let mut scoped = TableBuilder::new().with_naming(ClassNaming::Scoped("admin".to_string()));
+        let table = scoped.table_fixed("jobs",300).unwrap();
+        let row = scoped.create_row_fixed(table,&[None]).unwrap();
+        assert_eq!(scoped.geometry(row).unwrap().boxes[0].class,"admin-jobs_r0_c0");
+        tb.check_compatible(&scoped).unwrap();
+
+        let mut hashed = TableBuilder::new().with_naming(ClassNaming::Hashed("admin".to_string()));
+        let table = hashed.table_fixed("jobs",300).unwrap();
+        let row = hashed.create_row_fixed(table,&[None]).unwrap();
+        let class = hashed.geometry(row).unwrap().boxes[0].class.clone();
+        assert_eq!(class,format!("{}_r0_c0",ClassNaming::Hashed("admin".to_string()).prefix("jobs")));
+        assert_ne!(ClassNaming::Hashed("admin".to_string()).prefix("jobs"),ClassNaming::Hashed("public".to_string()).prefix("jobs"));
+        tb.check_compatible(&hashed).unwrap();
+        assert!(matches!(TableBuilder::new().with_naming(ClassNaming::Scoped("a.b".to_string())).table_fixed("jobs",300),Err(TableError::InvalidUid(_))));
+    }
+
+    #[test]
+    fn write_row() {
+        struct Full;
+        impl io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> { Err(io::Error::new(io::ErrorKind::WriteZero,"full")) }
+            fn flush(&mut self) -> io::Result<()> { Ok(()) }
+        }
+        let mut tb = TableBuilder::new();
+        let table = tb.table_fixed("logs",300).unwrap();
+        let row = tb.create_row_fixed_with(table,&[None,Some(60)],&[Overflow::Ellipsis]).unwrap();
+        let mut drawer = TableDrawer::new();
+        let values = ["<i>a 'b'</i>","c"];
+        let mut out = Vec::new();
+        tb.write_row_io(&mut out,row,"logs_row",&RowOptions::new(),&values,&mut drawer).unwrap();
+        let owned = tb.row(row,"logs_row",values.iter().map(|v| v.to_string()).collect(),&mut drawer).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),owned);
+        assert_eq!(owned,"<div class='logs_row'>\n<div class='logs_r0_c0' title='a &#39;b&#39;'><i>a 'b'</i></div>\n<div class='logs_r0_c1'>c</div>\n</div>");
+        let mut short = String::new();
+        tb.write_row(&mut short,row,"logs_row",&RowOptions::new(),&["x"],&mut drawer).unwrap();
+        assert!(short.contains("<div class='logs_r0_c1'>&nbsp;</div>"));
+        assert!(matches!(tb.write_row_io(&mut Full,row,"logs_row",&RowOptions::new(),&values,&mut drawer),Err(TableError::Io(e)) if e.kind() == io::ErrorKind::WriteZero));
+    }
+}
@@ -1,8 +1,22 @@
 use super::{
     classed,
-    HtmlProducer,Block,
+    HtmlProducer,Block,Element,Component,Dependency,
 };
 
+// what the tabs need to lay out, their look is in DefaultStyles
+const TABS_CSS: &str = ".tab_row { display: flex; align-items: flex-end; }
+.tab_finish { flex-grow: 1; }
+";
+
+// leaves tabClicked to the page, the component only navigates from data-tab-href
+const TABS_JS: &str = "(function() {
+    document.addEventListener('click', function(e) {
+        var t = e.target.closest('[data-tab-href]');
+        if (t) { window.location.href = t.getAttribute('data-tab-href'); }
+    });
+})();
+";
+
 pub struct Tabs {
     tabs: Vec<Tab>,
 }
@@ -14,7 +28,7 @@ pub struct Tab {
 }
 impl Tabs {
     pub fn new(_producer: &mut HtmlProducer, tabs: Vec<Tab>) -> Tabs {
-        Tabs{ tabs: tabs }
+        Tabs{ tabs }
     }
     pub fn set_active(&mut self, name: &str) {
         for tab in &mut self.tabs {
@@ -27,7 +41,12 @@ impl Tabs {
         self.tabs.push(tab);
         self
     }*/
+    // onclick calls tabClicked(href) of the page, CSP pages delegate it
     pub fn blocks(&self) -> Block {
+        self.markup(false)
+    }
+    // the component navigates from data-tab-href with its own script
+    fn markup(&self, delegated: bool) -> Block {
         let mut bl = Block::new("tab_row");
         for tab in &self.tabs {
             let act = match tab.active {
@@ -43,9 +62,11 @@ impl Tabs {
                     }
                     t
                 });
-                if !tab.active {
-                    bl = bl.onclick(format!("tabClicked(\"{}\");",tab.href));
-                }
+                bl = match (tab.active,delegated) {
+                    (true,_) => bl,
+                    (false,true) => bl.data("tab-href",&tab.href),
+                    (false,false) => bl.onclick(format!("tabClicked(\"{}\");",tab.href)),
+                };
                 bl
             });
         }
@@ -53,3 +74,11 @@ impl Tabs {
         Block::new("tabs").sub(bl)
     }
 }
+impl Component for Tabs {
+    fn render(&self) -> Element {
+        self.markup(true).into()
+    }
+    fn dependencies(&self) -> Vec<Dependency> {
+        vec![Dependency::new("tabs").css(TABS_CSS).js(TABS_JS)]
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt::Write;
+
+use super::{Element,Component,Dependency,escape};
+
+// Colors, fonts and spacing as css custom properties (--ui-*). Built-in components
+// use them with fallbacks, so they also work on pages without a theme.
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+impl ColorScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Palette {
+    pub background: String,
+    pub surface: String,
+    pub text: String,
+    pub muted: String,
+    pub border: String,
+    pub accent: String,
+    pub success: String,
+    pub warning: String,
+    pub danger: String,
+    pub row_alt: String,
+    pub row_hover: String,
+}
+impl Palette {
+    pub fn light() -> Palette {
+        Palette {
+            background: "#ffffff".to_string(),
+            surface: "#f3f4f6".to_string(),
+            text: "#1f2328".to_string(),
+            muted: "#6b7280".to_string(),
+            border: "#d0d7de".to_string(),
+            accent: "#2f6feb".to_string(),
+            success: "#1a7f37".to_string(),
+            warning: "#9a6700".to_string(),
+            danger: "#cf222e".to_string(),
+            row_alt: "#f8f9fa".to_string(),
+            row_hover: "#eef3fd".to_string(),
+        }
+    }
+    pub fn dark() -> Palette {
+        Palette {
+            background: "#0d1117".to_string(),
+            surface: "#161b22".to_string(),
+            text: "#e6edf3".to_string(),
+            muted: "#8b949e".to_string(),
+            border: "#30363d".to_string(),
+            accent: "#4493f8".to_string(),
+            success: "#3fb950".to_string(),
+            warning: "#d29922".to_string(),
+            danger: "#f85149".to_string(),
+            row_alt: "#11161d".to_string(),
+            row_hover: "#1c2633".to_string(),
+        }
+    }
+    fn vars(&self) -> [(&'static str,&str); 11] {
+        [
+            ("bg",&self.background),
+            ("surface",&self.surface),
+            ("text",&self.text),
+            ("muted",&self.muted),
+            ("border",&self.border),
+            ("accent",&self.accent),
+            ("success",&self.success),
+            ("warning",&self.warning),
+            ("danger",&self.danger),
+            ("row-alt",&self.row_alt),
+            ("row-hover",&self.row_hover),
+        ]
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Theme {
+    name: String,
+    scheme: ColorScheme,
+    palette: Palette,
+    font: String,
+    mono_font: String,
+    font_size: String,
+    spacing: String,
+    radius: String,
+}
+impl Theme {
+    // the name goes into the [data-theme='..'] selector, other characters are dropped
+    pub fn new<T: ToString>(name: T, scheme: ColorScheme, palette: Palette) -> Theme {
+        Theme {
+            name: name.to_string().chars().filter(|c| c.is_alphanumeric() || (*c == '_') || (*c == '-')).collect(),
+            scheme,
+            palette,
+            font: "system-ui, -apple-system, 'Segoe UI', sans-serif".to_string(),
+            mono_font: "ui-monospace, 'SFMono-Regular', Menlo, monospace".to_string(),
+            font_size: "14px".to_string(),
+            spacing: "4px".to_string(),
+            radius: "4px".to_string(),
+        }
+    }
+    pub fn light() -> Theme {
+        Theme::new("light",ColorScheme::Light,Palette::light())
+    }
+    pub fn dark() -> Theme {
+        Theme::new("dark",ColorScheme::Dark,Palette::dark())
+    }
+    pub fn font<T: ToString>(mut self, font: T) -> Theme {
+        self.font = font.to_string();
+        self
+    }
+    pub fn mono_font<T: ToString>(mut self, font: T) -> Theme {
+        self.mono_font = font.to_string();
+        self
+    }
+    pub fn font_size<T: ToString>(mut self, size: T) -> Theme {
+        self.font_size = size.to_string();
+        self
+    }
+    pub fn spacing<T: ToString>(mut self, spacing: T) -> Theme {
+        self.spacing = spacing.to_string();
+        self
+    }
+    pub fn radius<T: ToString>(mut self, radius: T) -> Theme {
+        self.radius = radius.to_string();
+        self
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn scheme(&self) -> ColorScheme {
+        self.scheme
+    }
+
+    fn write_vars(&self, res: &mut String, indent: &str) {
+        writeln!(res,"{}color-scheme: {};",indent,self.scheme.as_str()).ok();
+        for (n,v) in self.palette.vars() {
+            writeln!(res,"{}--ui-{}: {};",indent,n,v).ok();
+        }
+        for (n,v) in [("font",&self.font),("mono",&self.mono_font),("font-size",&self.font_size),("spacing",&self.spacing),("radius",&self.radius)] {
+            writeln!(res,"{}--ui-{}: {};",indent,n,v).ok();
+        }
+    }
+}
+
+const THEME_CSS: &str = "body { background: var(--ui-bg); color: var(--ui-text); font-family: var(--ui-font); font-size: var(--ui-font-size); }
+pre, code { font-family: var(--ui-mono); }
+";
+
+// the stored choice is applied before the body is parsed, so there is no flash of the default theme
+const TOGGLE_JS: &str = "(function() {
+    var t = null;
+    try { t = localStorage.getItem('ui-theme'); } catch (e) {}
+    if (t) { document.documentElement.setAttribute('data-theme', t); }
+})();
+document.addEventListener('DOMContentLoaded', function() {
+    var t = document.documentElement.getAttribute('data-theme') || '';
+    document.querySelectorAll('select[data-theme-toggle]').forEach(function(s) { s.value = t; });
+});
+document.addEventListener('change', function(e) {
+    if (!e.target.hasAttribute('data-theme-toggle')) { return; }
+    var t = e.target.value;
+    if (t) { document.documentElement.setAttribute('data-theme', t); } else { document.documentElement.removeAttribute('data-theme'); }
+    try { if (t) { localStorage.setItem('ui-theme', t); } else { localStorage.removeItem('ui-theme'); } } catch (e) {}
+});
+";
+
+const TOGGLE_CSS: &str = ".theme_toggle { background: var(--ui-surface, inherit); color: inherit; border: 1px solid var(--ui-border, #ccc); border-radius: var(--ui-radius, 4px); }
+";
+
+// Registered themes: the light and dark defaults follow prefers-color-scheme,
+// any theme can be forced with data-theme='name' on the html element.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Themes {
+    themes: Vec<Theme>,
+    light: String,
+    dark: String,
+}
+impl Default for Themes {
+    fn default() -> Themes {
+        Themes {
+            themes: vec![Theme::light(),Theme::dark()],
+            light: "light".to_string(),
+            dark: "dark".to_string(),
+        }
+    }
+}
+impl Themes {
+    pub fn new() -> Themes {
+        Themes::default()
+    }
+    // a theme with the same name is replaced
+    pub fn register(mut self, theme: Theme) -> Themes {
+        match self.themes.iter().position(|t| t.name == theme.name) {
+            Some(i) => self.themes[i] = theme,
+            None => self.themes.push(theme),
+        }
+        self
+    }
+    // unknown names are ignored
+    pub fn default_light(mut self, name: &str) -> Themes {
+        if self.get(name).is_some() { self.light = name.to_string(); }
+        self
+    }
+    pub fn default_dark(mut self, name: &str) -> Themes {
+        if self.get(name).is_some() { self.dark = name.to_string(); }
+        self
+    }
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|t| t.name == name)
+    }
+    pub fn names(&self) -> Vec<&str> {
+        self.themes.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    pub fn css(&self) -> String {
+        let mut res = String::new();
+        if let Some(t) = self.get(&self.light) {
+            res += ":root {\n";
+            t.write_vars(&mut res,"    ");
+            res += "}\n";
+        }
+        if let Some(t) = self.get(&self.dark) {
+            res += "@media (prefers-color-scheme: dark) {\n    :root {\n";
+            t.write_vars(&mut res,"        ");
+            res += "    }\n}\n";
+        }
+        for t in &self.themes {
+            writeln!(res,":root[data-theme='{}'] {{",t.name).ok();
+            t.write_vars(&mut res,"    ");
+            res += "}\n";
+        }
+        res += THEME_CSS;
+        res
+    }
+    pub fn dependency(&self) -> Dependency {
+        Dependency::new("theme").css(&self.css())
+    }
+    pub fn toggle(&self) -> ThemeToggle {
+        ThemeToggle {
+            names: self.themes.iter().map(|t| t.name.clone()).collect(),
+        }
+    }
+}
+
+// a select between the registered themes and "auto", the choice is kept in localStorage
+#[derive(Debug,Clone)]
+pub struct ThemeToggle {
+    names: Vec<String>,
+}
+impl Component for ThemeToggle {
+    fn render(&self) -> Element {
+        let mut el = Element::new("select").class("theme_toggle").flag("data-theme-toggle").attr("aria-label","Theme");
+        el.push(Element::new("option").attr("value","").text("auto"));
+        for n in &self.names {
+            el.push(Element::new("option").attr("value",n).text(n));
+        }
+        el
+    }
+    fn dependencies(&self) -> Vec<Dependency> {
+        vec![Dependency::new("theme_toggle").css(TOGGLE_CSS).js(TOGGLE_JS)]
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum BadgeKind {
+    Neutral,
+    Accent,
+    Success,
+    Warning,
+    Danger,
+}
+impl BadgeKind {
+    fn class(&self) -> &'static str {
+        match self {
+            BadgeKind::Neutral => "badge_neutral",
+            BadgeKind::Accent => "badge_accent",
+            BadgeKind::Success => "badge_success",
+            BadgeKind::Warning => "badge_warning",
+            BadgeKind::Danger => "badge_danger",
+        }
+    }
+}
+
+const BADGE_CSS: &str = ".badge { display: inline-block; padding: 0 var(--ui-spacing, 4px); border-radius: var(--ui-radius, 4px); font-size: 85%; color: #fff; }
+.badge_neutral { background: var(--ui-muted, #888); }
+.badge_accent { background: var(--ui-accent, #2f6feb); }
+.badge_success { background: var(--ui-success, #1a7f37); }
+.badge_warning { background: var(--ui-warning, #9a6700); }
+.badge_danger { background: var(--ui-danger, #cf222e); }
+";
+
+#[derive(Debug,Clone)]
+pub struct Badge {
+    text: String,
+    kind: BadgeKind,
+}
+impl Badge {
+    pub fn new<T: ToString>(text: T, kind: BadgeKind) -> Badge {
+        Badge { text: text.to_string(), kind }
+    }
+    // the markup as a string, for cells and block texts
+    pub fn html(&self) -> String {
+        format!("<span class='badge {}'>{}</span>",self.kind.class(),escape(&self.text))
+    }
+    pub fn dependency() -> Dependency {
+        Dependency::new("badge").css(BADGE_CSS)
+    }
+}
+impl Component for Badge {
+    fn render(&self) -> Element {
+        Element::new("span").class("badge").class(self.kind.class()).text(&self.text)
+    }
+    fn dependencies(&self) -> Vec<Dependency> {
+        vec![Badge::dependency()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes() {
+        let wall = Theme::new("wall",ColorScheme::Dark,Palette { background: "#000".to_string(), ..Palette::dark() }).font_size("20px");
+        let themes = Themes::new().register(wall).default_dark("wall").default_light("missing");
+        assert_eq!(themes.names(),vec!["light","dark","wall"]);
+        let css = themes.css();
+        assert!(css.starts_with(":root {\n    color-scheme: light;\n    --ui-bg: #ffffff;\n"));
+        assert!(css.contains("@media (prefers-color-scheme: dark) {\n    :root {\n        color-scheme: dark;\n        --ui-bg: #000;\n"));
+        assert!(css.contains(":root[data-theme='wall'] {\n    color-scheme: dark;\n    --ui-bg: #000;\n"));
+        let evil = Themes::new().register(Theme::new("x'] { } body { display: none; } [a='",ColorScheme::Light,Palette::light())).css();
+        assert!(evil.contains(":root[data-theme='xbodydisplaynonea'] {\n"));
+        assert!(css.contains("    --ui-font-size: 20px;\n"));
+        assert_eq!(themes.toggle().render().to_string(),"<select class='theme_toggle' data-theme-toggle aria-label='Theme'>\
+<option value=''>auto</option><option value='light'>light</option><option value='dark'>dark</option><option value='wall'>wall</option></select>");
+        assert_eq!(Badge::new("<3",BadgeKind::Danger).html(),"<span class='badge badge_danger'>&lt;3</span>");
+        assert_eq!(Badge::new("<3",BadgeKind::Danger).render().to_string(),Badge::new("<3",BadgeKind::Danger).html());
+    }
+}